@@ -0,0 +1,492 @@
+use std::{env, fmt::Write, fs, path::Path};
+
+// run-length encoding, shared with the cart, which decodes what's encoded here
+#[allow(dead_code)]
+#[path = "src/rle.rs"]
+mod rle;
+
+use rle::rle_encode;
+
+struct TextureSource {
+    path: &'static str,
+    rust_name: &'static str,
+    // the size of each sprite or tile in the image, which must be a whole number of them
+    frame_width: u32,
+    frame_height: u32,
+    // stores the texture run-length encoded, to be unpacked when it's first used (see
+    // `compress.rs`)
+    compressed: bool,
+}
+
+// Images that are turned into textures at build time. Images with up to 2 colors become a
+// `Texture2Color`, and images with 3 or 4 colors become a `Texture4Color`, or a
+// `CompressedTexture2Color` or `CompressedTexture4Color` if they're compressed.
+const TEXTURES: &[TextureSource] = &[TextureSource {
+    path: "Sprite-0002.png",
+    rust_name: "BALL",
+    frame_width: 16,
+    frame_height: 16,
+    compressed: false,
+}];
+
+// When set, the build prints how much space each texture takes up, and how much compressing it
+// saves or would save. The report is always written to `asset_sizes.txt` in the output directory.
+const SIZE_REPORT_VAR: &str = "ASSET_SIZE_REPORT";
+
+struct FontSource {
+    path: &'static str,
+    // lists the characters in the atlas, one line per row of glyphs (see `parse_font_chars`)
+    chars_path: &'static str,
+    rust_name: &'static str,
+    // stores the texture run-length encoded, like `TextureSource::compressed`
+    compressed: bool,
+}
+
+// Fonts whose textures, glyph widths and character maps are generated from their source at
+// build time. Fonts with up to 2 colors become a `Texture2Color`, and fonts with 3 or 4 colors
+// (for an outline or a drop shadow) become a `Texture4Color`, where color 1 is the glyph itself.
+const FONTS: &[FontSource] = &[
+    FontSource {
+        path: "bold 7x5 font.png",
+        chars_path: "bold 7x5 font.txt",
+        rust_name: "BOLD_7X5_FONT",
+        compressed: false,
+    },
+    FontSource {
+        path: "bold 7x5 font outline.png",
+        chars_path: "bold 7x5 font.txt",
+        rust_name: "BOLD_7X5_OUTLINE_FONT",
+        compressed: true,
+    },
+];
+
+// Named glyphs in a font's character list are given code points from the Private Use Area
+const FIRST_PRIVATE_USE_CHAR: u32 = 0xe000;
+
+struct Image {
+    width: u32,
+    height: u32,
+    // one color index per pixel, in row-major order
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    fn pixel(&self, x: u32, y: u32) -> u8 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+struct TextureSize {
+    path: &'static str,
+    raw: usize,
+    compressed: usize,
+    is_compressed: bool,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed={}", SIZE_REPORT_VAR);
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let mut sizes = Vec::new();
+
+    let mut textures = String::new();
+    for texture in TEXTURES {
+        println!("cargo:rerun-if-changed={}", texture.path);
+        let image = load_png(texture.path);
+        if image.width == 0
+            || image.height == 0
+            || !image.width.is_multiple_of(texture.frame_width)
+            || !image.height.is_multiple_of(texture.frame_height)
+        {
+            panic!(
+                "{} is {}x{}, which isn't a whole number of {}x{} frames",
+                texture.path, image.width, image.height, texture.frame_width, texture.frame_height
+            );
+        }
+        sizes.push(write_texture(
+            &mut textures,
+            texture.path,
+            texture.rust_name,
+            &image,
+            texture.compressed,
+        ));
+    }
+    fs::write(Path::new(&out_dir).join("textures.rs"), textures).unwrap();
+
+    let mut fonts = String::new();
+    for font in FONTS {
+        println!("cargo:rerun-if-changed={}", font.path);
+        println!("cargo:rerun-if-changed={}", font.chars_path);
+        let image = load_png(font.path);
+        let chars = fs::read_to_string(font.chars_path)
+            .unwrap_or_else(|e| panic!("couldn't open {}: {}", font.chars_path, e));
+        let rows = parse_font_chars(font.chars_path, &chars);
+        sizes.push(write_font(&mut fonts, font, &image, &rows));
+    }
+    fs::write(Path::new(&out_dir).join("fonts.rs"), fonts).unwrap();
+
+    let report = size_report(&sizes);
+    if env::var_os(SIZE_REPORT_VAR).is_some() {
+        for line in report.lines() {
+            println!("cargo:warning={}", line);
+        }
+    }
+    fs::write(Path::new(&out_dir).join("asset_sizes.txt"), report).unwrap();
+}
+
+/// Lists the size of each texture, raw and run-length encoded, and the total stored in the cart.
+fn size_report(sizes: &[TextureSize]) -> String {
+    let mut report = String::new();
+    let (mut total, mut total_raw) = (0, 0);
+    for size in sizes {
+        let saved = 100 - (size.compressed * 100).div_ceil(size.raw.max(1)) as i64;
+        let status = if size.is_compressed {
+            "compressed"
+        } else {
+            "raw"
+        };
+        writeln!(
+            report,
+            "{}: {} bytes raw, {} bytes compressed ({}% saved), stored {}",
+            size.path, size.raw, size.compressed, saved, status
+        )
+        .unwrap();
+        total_raw += size.raw;
+        total += if size.is_compressed {
+            size.compressed
+        } else {
+            size.raw
+        };
+    }
+    writeln!(
+        report,
+        "total: {} bytes stored, {} bytes unpacked",
+        total, total_raw
+    )
+    .unwrap();
+    report
+}
+
+/// Loads a PNG as color indices, the same way `w4 png2src` assigns them: indexed images keep
+/// their palette indices, and other images number their distinct colors with transparent first,
+/// then from lightest to darkest.
+fn load_png(path: &str) -> Image {
+    let file = fs::File::open(path).unwrap_or_else(|e| panic!("couldn't open {}: {}", path, e));
+    let mut decoder = png::Decoder::new(file);
+    let indexed = match decoder.read_header_info() {
+        Ok(info) => info.color_type == png::ColorType::Indexed,
+        Err(e) => panic!("couldn't decode {}: {}", path, e),
+    };
+    if indexed {
+        decoder.set_transformations(png::Transformations::IDENTITY);
+    } else {
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+    }
+    let mut reader = decoder
+        .read_info()
+        .unwrap_or_else(|e| panic!("couldn't decode {}: {}", path, e));
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buf).unwrap();
+    let (width, height) = (frame.width, frame.height);
+    let bytes = &buf[..frame.buffer_size()];
+
+    let pixels = if indexed {
+        let bit_depth = frame.bit_depth as usize;
+        let per_byte = 8 / bit_depth;
+        let mask = ((1u16 << bit_depth) - 1) as u8;
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for row in bytes.chunks(frame.line_size) {
+            for x in 0..width as usize {
+                let shift = 8 - bit_depth * (x % per_byte + 1);
+                pixels.push((row[x / per_byte] >> shift) & mask);
+            }
+        }
+        pixels
+    } else {
+        let channels = frame.color_type.samples();
+        let rgba: Vec<[u8; 4]> = bytes
+            .chunks(channels)
+            .map(|p| match channels {
+                1 => [p[0], p[0], p[0], 255],
+                2 => [p[0], p[0], p[0], p[1]],
+                3 => [p[0], p[1], p[2], 255],
+                _ => [p[0], p[1], p[2], p[3]],
+            })
+            .map(|p| if p[3] == 0 { [0; 4] } else { p })
+            .collect();
+        let mut colors = rgba.clone();
+        colors.sort_by_key(|&[r, g, b, a]| {
+            let luminance = 299 * r as u32 + 587 * g as u32 + 114 * b as u32;
+            (a != 0, u32::MAX - luminance)
+        });
+        colors.dedup();
+        rgba.iter()
+            .map(|p| colors.iter().position(|c| c == p).unwrap() as u8)
+            .collect()
+    };
+    Image {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// The number of bits per pixel needed to store an image with the colors it uses.
+fn bits_per_pixel(path: &str, image: &Image) -> u32 {
+    match image.pixels.iter().max().copied().unwrap_or(0) {
+        0..=1 => 1,
+        2..=3 => 2,
+        max => panic!(
+            "{} uses {} colors, but WASM-4 textures can have at most 4",
+            path,
+            max as u32 + 1
+        ),
+    }
+}
+
+/// Packs an image into a `Texture2Color` or `Texture4Color` constant, in the format used by `blit`,
+/// or a `CompressedTexture2Color` or `CompressedTexture4Color` static holding the run-length
+/// encoded bytes.
+fn write_texture(
+    out: &mut String,
+    path: &'static str,
+    rust_name: &str,
+    image: &Image,
+    compressed: bool,
+) -> TextureSize {
+    let bpp = bits_per_pixel(path, image);
+    let mut data = vec![0u8; (image.pixels.len() * bpp as usize).div_ceil(8)];
+    for (i, &pixel) in image.pixels.iter().enumerate() {
+        let bit = i * bpp as usize;
+        data[bit / 8] |= pixel << (8 - bpp as usize - bit % 8);
+    }
+    let encoded = rle_encode(&data);
+    let (texture_type, blit_format) = match bpp {
+        1 => ("Texture2Color", "BLIT_1BPP"),
+        _ => ("Texture4Color", "BLIT_2BPP"),
+    };
+
+    if compressed {
+        writeln!(out, "// {} ({}, run-length encoded)", path, blit_format).unwrap();
+        write_bytes(out, &format!("{}_TEXTURE_RLE", rust_name), &encoded);
+        writeln!(
+            out,
+            "static {}_TEXTURE: crate::compress::Compressed{} =",
+            rust_name, texture_type
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    crate::compress::Compressed{}::new(&{}_TEXTURE_RLE, {}, {}, {});",
+            texture_type,
+            rust_name,
+            data.len(),
+            image.width,
+            image.height
+        )
+        .unwrap();
+    } else {
+        writeln!(out, "// {} ({})", path, blit_format).unwrap();
+        write_bytes(out, &format!("{}_TEXTURE_DATA", rust_name), &data);
+        writeln!(
+            out,
+            "const {}_TEXTURE: {}<'static> = {} {{",
+            rust_name, texture_type, texture_type
+        )
+        .unwrap();
+        writeln!(out, "    data: &{}_TEXTURE_DATA,", rust_name).unwrap();
+        writeln!(out, "    stride: {},", image.width).unwrap();
+        writeln!(out, "    height: {},", image.height).unwrap();
+        writeln!(out, "}};").unwrap();
+    }
+    TextureSize {
+        path,
+        raw: data.len(),
+        compressed: encoded.len(),
+        is_compressed: compressed,
+    }
+}
+
+fn write_bytes(out: &mut String, rust_name: &str, bytes: &[u8]) {
+    writeln!(out, "const {}: [u8; {}] = [", rust_name, bytes.len()).unwrap();
+    for row in bytes.chunks(16) {
+        let bytes: Vec<String> = row.iter().map(|b| format!("{:#04x},", b)).collect();
+        writeln!(out, "    {}", bytes.join(" ")).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+}
+
+struct FontChar {
+    c: char,
+    name: Option<String>,
+}
+
+/// Parses a font's character list, which has one line per row of glyphs in the atlas. `\\`, `\{`
+/// and `\u{XXXX}` are escapes, and `{NAME}` is a glyph with no standard character, which is given
+/// the next code point in the Private Use Area and exported as a `char` constant.
+fn parse_font_chars(path: &str, source: &str) -> Vec<Vec<FontChar>> {
+    let mut next_private_use = FIRST_PRIVATE_USE_CHAR;
+    let mut rows = Vec::new();
+    for (line_number, line) in source.lines().enumerate() {
+        let error = |message: &str| -> ! { panic!("{}:{}: {}", path, line_number + 1, message) };
+        let mut row = Vec::new();
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            let font_char = match c {
+                '\\' => match chars.next() {
+                    Some('u') => {
+                        let rest = chars.as_str();
+                        let end = rest.find('}').unwrap_or_else(|| error("unclosed \\u{"));
+                        let hex = rest[..end]
+                            .strip_prefix('{')
+                            .unwrap_or_else(|| error("expected { after \\u"));
+                        let c = u32::from_str_radix(hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .unwrap_or_else(|| error("invalid code point"));
+                        chars = rest[end + 1..].chars();
+                        FontChar { c, name: None }
+                    }
+                    Some(c @ ('\\' | '{')) => FontChar { c, name: None },
+                    _ => error("unknown escape"),
+                },
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').unwrap_or_else(|| error("unclosed {"));
+                    let name = &rest[..end];
+                    if name.is_empty()
+                        || !name
+                            .chars()
+                            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+                    {
+                        error("glyph names must be SCREAMING_SNAKE_CASE");
+                    }
+                    chars = rest[end + 1..].chars();
+                    let c = char::from_u32(next_private_use).unwrap();
+                    next_private_use += 1;
+                    FontChar {
+                        c,
+                        name: Some(name.to_string()),
+                    }
+                }
+                c => FontChar { c, name: None },
+            };
+            row.push(font_char);
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+/// Measures the inked columns of each glyph cell and emits the texture, `Glyph` table, character
+/// map and named glyph constants for the font.
+fn write_font(
+    out: &mut String,
+    font: &FontSource,
+    image: &Image,
+    rows: &[Vec<FontChar>],
+) -> TextureSize {
+    let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0) as u32;
+    if columns == 0 || !image.width.is_multiple_of(columns) {
+        panic!(
+            "{} is {} pixels wide, which doesn't divide into {} glyphs per row",
+            font.path, image.width, columns
+        );
+    }
+    if !image.height.is_multiple_of(rows.len() as u32) {
+        panic!(
+            "{} is {} pixels tall, which doesn't divide into {} rows of glyphs",
+            font.path,
+            image.height,
+            rows.len()
+        );
+    }
+    let cell_width = image.width / columns;
+    let cell_height = image.height / rows.len() as u32;
+
+    // map each character to its glyph index, merging runs of consecutive characters into ranges
+    let mut char_indices: Vec<(char, u32)> = rows
+        .iter()
+        .flatten()
+        .enumerate()
+        .map(|(i, font_char)| (font_char.c, i as u32))
+        .collect();
+    char_indices.sort();
+    if let Some(pair) = char_indices.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        panic!("{} lists {:?} more than once", font.chars_path, pair[0].0);
+    }
+    let mut ranges: Vec<(char, u32, u32)> = Vec::new();
+    for &(c, glyph_index) in &char_indices {
+        match ranges.last_mut() {
+            Some((first, len, first_index))
+                if *first as u32 + *len == c as u32 && *first_index + *len == glyph_index =>
+            {
+                *len += 1
+            }
+            _ => ranges.push((c, 1, glyph_index)),
+        }
+    }
+
+    let size = write_texture(out, font.path, font.rust_name, image, font.compressed);
+    writeln!(
+        out,
+        "const {}_GLYPH_HEIGHT: u32 = {};",
+        font.rust_name, cell_height
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "const {}_GLYPHS: [Glyph; {}] = [",
+        font.rust_name,
+        char_indices.len()
+    )
+    .unwrap();
+    for (row_index, row) in rows.iter().enumerate() {
+        let cell_y = row_index as u32 * cell_height;
+        for (column, font_char) in row.iter().enumerate() {
+            let cell_x = column as u32 * cell_width;
+            let inked: Vec<u32> = (0..cell_width)
+                .filter(|&x| (0..cell_height).any(|y| image.pixel(cell_x + x, cell_y + y) != 0))
+                .collect();
+            let (src_x, width) = match (inked.first(), inked.last()) {
+                (Some(first), Some(last)) => (cell_x + first, last - first + 1),
+                // blank glyphs like the space get half a cell so that words stay apart
+                _ => (cell_x, cell_width.div_ceil(2)),
+            };
+            writeln!(
+                out,
+                "    Glyph {{ src_x: {}, src_y: {}, width: {} }}, // {:?}",
+                src_x, cell_y, width, font_char.c
+            )
+            .unwrap();
+        }
+    }
+    writeln!(out, "];").unwrap();
+    writeln!(
+        out,
+        "const {}_CHAR_MAP: [CharRange; {}] = [",
+        font.rust_name,
+        ranges.len()
+    )
+    .unwrap();
+    for (first, len, glyph_index) in ranges {
+        writeln!(
+            out,
+            "    CharRange {{ first: {:?}, len: {}, glyph_index: {} }},",
+            first, len, glyph_index
+        )
+        .unwrap();
+    }
+    writeln!(out, "];").unwrap();
+    for font_char in rows.iter().flatten() {
+        if let Some(name) = &font_char.name {
+            writeln!(
+                out,
+                "pub const {}_{}: char = {:?};",
+                font.rust_name, name, font_char.c
+            )
+            .unwrap();
+        }
+    }
+    size
+}
@@ -0,0 +1,369 @@
+use core::fmt;
+
+use crate::{
+    input::{Button, Input, MouseButton},
+    sprite::Texture,
+    ui::Ui,
+    wasm4::{self, DISK_SIZE},
+};
+
+// the first byte of saved bindings, to tell them apart from whatever else was on the disk
+const SAVE_MAGIC: u8 = 0xb1;
+const PLAYERS: usize = 4;
+
+/// A game's set of actions, such as jumping or opening the pause menu, usually a fieldless enum.
+pub trait Action: Copy + PartialEq + 'static {
+    /// Every action, in the order they're listed in the rebinding menu and saved in.
+    const ALL: &'static [Self];
+
+    /// The action's name in the rebinding menu.
+    fn name(self) -> &'static str;
+}
+
+/// The buttons that trigger an action: a set of gamepad buttons that all have to be held
+/// together, or a set of mouse buttons that all have to be held together. Either set can be
+/// empty, and a binding with both empty never triggers.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Binding {
+    // as in the GAMEPAD and MOUSE_BUTTONS registers
+    pub buttons: u8,
+    pub mouse_buttons: u8,
+}
+
+impl Binding {
+    pub const NONE: Binding = Binding {
+        buttons: 0,
+        mouse_buttons: 0,
+    };
+
+    pub const fn button(button: Button) -> Self {
+        Self::combo(&[button])
+    }
+
+    /// A binding to several gamepad buttons held together.
+    pub const fn combo(buttons: &[Button]) -> Self {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < buttons.len() {
+            bits |= buttons[i].bit();
+            i += 1;
+        }
+        Self {
+            buttons: bits,
+            mouse_buttons: 0,
+        }
+    }
+
+    pub const fn mouse(button: MouseButton) -> Self {
+        Self {
+            buttons: 0,
+            mouse_buttons: button.bit(),
+        }
+    }
+
+    /// This binding, which can also be triggered by `button` on the mouse.
+    pub const fn or_mouse(self, button: MouseButton) -> Self {
+        Self {
+            buttons: self.buttons,
+            mouse_buttons: self.mouse_buttons | button.bit(),
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    fn active(&self, gamepad_bits: u8, mouse_bits: u8) -> bool {
+        let all_held = |mask: u8, bits: u8| mask != 0 && bits & mask == mask;
+        all_held(self.buttons, gamepad_bits) || all_held(self.mouse_buttons, mouse_bits)
+    }
+
+    /// This binding with the parts of `other` that aren't empty put in place of its own, so
+    /// binding a mouse button keeps the gamepad buttons and the other way around.
+    pub fn replaced_with(self, other: Binding) -> Self {
+        Self {
+            buttons: if other.buttons != 0 {
+                other.buttons
+            } else {
+                self.buttons
+            },
+            mouse_buttons: if other.mouse_buttons != 0 {
+                other.mouse_buttons
+            } else {
+                self.mouse_buttons
+            },
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    /// Lists the buttons as they'd appear in a menu, such as `X+DOWN/LMB`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_none() {
+            return f.write_str("-");
+        }
+        let buttons = Button::ALL
+            .into_iter()
+            .filter(|button| self.buttons & button.bit() != 0)
+            .map(|button| match button {
+                Button::X => "X",
+                Button::Z => "Z",
+                Button::Left => "LEFT",
+                Button::Right => "RIGHT",
+                Button::Up => "UP",
+                Button::Down => "DOWN",
+            });
+        let mouse_buttons = MouseButton::ALL
+            .into_iter()
+            .filter(|button| self.mouse_buttons & button.bit() != 0)
+            .map(|button| match button {
+                MouseButton::Left => "LMB",
+                MouseButton::Right => "RMB",
+                MouseButton::Middle => "MMB",
+            });
+        let mut separator = "";
+        for (i, name) in buttons.enumerate() {
+            f.write_str(if i == 0 { separator } else { "+" })?;
+            f.write_str(name)?;
+            separator = "/";
+        }
+        for (i, name) in mouse_buttons.enumerate() {
+            f.write_str(if i == 0 { separator } else { "+" })?;
+            f.write_str(name)?;
+        }
+        Ok(())
+    }
+}
+
+/// The default bindings for a player. Actions that aren't listed start out unbound.
+pub type Profile<A> = &'static [(A, Binding)];
+
+/// Each player's bindings from actions to buttons, which game code asks about actions through
+/// instead of checking buttons itself. Each player reads their own gamepad, and every player
+/// reads the mouse, since there's only one.
+pub struct Controls<A: Action> {
+    defaults: [Profile<A>; PLAYERS],
+    // indexed by player, then by position in `A::ALL`
+    bindings: [Vec<Binding>; PLAYERS],
+}
+
+impl<A: Action> Controls<A> {
+    pub fn new(defaults: [Profile<A>; PLAYERS]) -> Self {
+        let mut controls = Self {
+            defaults,
+            bindings: Default::default(),
+        };
+        for player in 0..PLAYERS {
+            controls.reset(player);
+        }
+        controls
+    }
+
+    fn index(action: A) -> usize {
+        A::ALL
+            .iter()
+            .position(|&other| other == action)
+            .expect("action missing from Action::ALL")
+    }
+
+    /// Puts `player`'s bindings back to their default profile.
+    pub fn reset(&mut self, player: usize) {
+        let bindings = &mut self.bindings[player];
+        bindings.clear();
+        bindings.resize(A::ALL.len(), Binding::NONE);
+        for &(action, binding) in self.defaults[player] {
+            bindings[Self::index(action)] = binding;
+        }
+    }
+
+    pub fn binding(&self, player: usize, action: A) -> Binding {
+        self.bindings[player][Self::index(action)]
+    }
+
+    pub fn bind(&mut self, player: usize, action: A, binding: Binding) {
+        self.bindings[player][Self::index(action)] = binding;
+    }
+
+    /// Whether `player` is holding down the buttons for `action` this frame.
+    pub fn held(&self, input: &Input, player: usize, action: A) -> bool {
+        let binding = self.binding(player, action);
+        binding.active(input.gamepad(player).bits(), input.mouse().bits())
+    }
+
+    /// Whether `player` finished pressing the buttons for `action` this frame.
+    pub fn pressed(&self, input: &Input, player: usize, action: A) -> bool {
+        self.held(input, player, action) && !self.held_last_frame(input, player, action)
+    }
+
+    /// Whether `player` let go of the buttons for `action` this frame.
+    pub fn released(&self, input: &Input, player: usize, action: A) -> bool {
+        !self.held(input, player, action) && self.held_last_frame(input, player, action)
+    }
+
+    fn held_last_frame(&self, input: &Input, player: usize, action: A) -> bool {
+        let binding = self.binding(player, action);
+        binding.active(
+            input.gamepad(player).previous_bits(),
+            input.mouse().previous_bits(),
+        )
+    }
+
+    /// How many bytes of persistent storage `save` takes up.
+    pub fn save_len(&self) -> usize {
+        2 + PLAYERS * A::ALL.len() * 2
+    }
+
+    /// Writes every player's bindings to persistent storage, starting `offset` bytes in, and
+    /// leaves the rest of the disk as it was. Returns false, writing nothing, if they don't fit.
+    pub fn save(&self, offset: usize) -> bool {
+        if offset + self.save_len() > DISK_SIZE as usize {
+            return false;
+        }
+        let mut disk = [0; DISK_SIZE as usize];
+        let len = unsafe { wasm4::diskr(disk.as_mut_ptr(), DISK_SIZE) } as usize;
+        let data = &mut disk[offset..offset + self.save_len()];
+        data[0] = SAVE_MAGIC;
+        // the count is saved in one byte
+        debug_assert!(A::ALL.len() <= u8::MAX as usize);
+        data[1] = A::ALL.len() as u8;
+        for (bytes, binding) in data[2..]
+            .chunks_exact_mut(2)
+            .zip(self.bindings.iter().flatten())
+        {
+            bytes.copy_from_slice(&[binding.buttons, binding.mouse_buttons]);
+        }
+        let len = len.max(offset + self.save_len());
+        unsafe { wasm4::diskw(disk.as_ptr(), len as u32) };
+        true
+    }
+
+    /// Reads bindings written by `save` from persistent storage, `offset` bytes in, returning
+    /// false and keeping the current bindings if there aren't any saved for this set of actions.
+    pub fn load(&mut self, offset: usize) -> bool {
+        let mut disk = [0; DISK_SIZE as usize];
+        let len = unsafe { wasm4::diskr(disk.as_mut_ptr(), DISK_SIZE) } as usize;
+        if len < offset + self.save_len() {
+            return false;
+        }
+        let data = &disk[offset..offset + self.save_len()];
+        if data[0] != SAVE_MAGIC || data[1] as usize != A::ALL.len() {
+            return false;
+        }
+        for (bytes, binding) in data[2..]
+            .chunks_exact(2)
+            .zip(self.bindings.iter_mut().flatten())
+        {
+            *binding = Binding {
+                buttons: bytes[0],
+                mouse_buttons: bytes[1],
+            };
+        }
+        true
+    }
+}
+
+/// Watches for the buttons a player wants to bind to an action. It waits until every button has
+/// been let go, so the press that started rebinding isn't caught, then collects every button
+/// pressed until they've all been let go again, which allows for combinations.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct BindingCapture {
+    ready: bool,
+    captured: Binding,
+}
+
+impl BindingCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes this frame's input from `player`'s gamepad and the mouse, returning the captured
+    /// binding once it's finished.
+    pub fn update(&mut self, input: &Input, player: usize) -> Option<Binding> {
+        let buttons = input.gamepad(player).bits();
+        let mouse_buttons = input.mouse().bits();
+        if !self.ready {
+            self.ready = buttons == 0 && mouse_buttons == 0;
+            return None;
+        }
+        self.captured.buttons |= buttons;
+        self.captured.mouse_buttons |= mouse_buttons;
+        if buttons == 0 && mouse_buttons == 0 && !self.captured.is_none() {
+            let captured = self.captured;
+            *self = Self::new();
+            return Some(captured);
+        }
+        None
+    }
+}
+
+/// A menu listing each action with its binding, where choosing an action waits for new buttons
+/// for it, followed by an item to go back to the defaults.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct RebindMenu {
+    // the action being rebound, as a position in `Action::ALL`, and the menu's focus beforehand
+    capturing: Option<(usize, usize)>,
+    capture: BindingCapture,
+}
+
+impl RebindMenu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the menu is waiting for new buttons, during which the game should ignore input.
+    pub fn is_capturing(&self) -> bool {
+        self.capturing.is_some()
+    }
+
+    /// Draws the menu with `ui` and handles rebinding `player`'s controls, returning whether
+    /// their bindings changed this frame, such as to save them. Call once per frame, after
+    /// `ui.begin`.
+    pub fn update<A: Action, T: Texture>(
+        &mut self,
+        ui: &mut Ui<T>,
+        input: &Input,
+        controls: &mut Controls<A>,
+        player: usize,
+        x: i32,
+        y: i32,
+    ) -> bool {
+        let mut changed = false;
+        if let Some((action, focus)) = self.capturing {
+            // the D-pad might be what's being bound, so it can't move the focus meanwhile
+            ui.set_focus(focus);
+            if let Some(binding) = self.capture.update(input, player) {
+                let action = A::ALL[action];
+                let binding = controls.binding(player, action).replaced_with(binding);
+                controls.bind(player, action, binding);
+                self.capturing = None;
+                changed = true;
+            }
+        }
+
+        let mut items: Vec<String> = A::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, action)| match self.capturing {
+                Some((capturing, _)) if capturing == i => format!("{} ...", action.name()),
+                _ => format!("{} {}", action.name(), controls.binding(player, *action)),
+            })
+            .collect();
+        items.push(String::from("Defaults"));
+        let items: Vec<&str> = items.iter().map(String::as_str).collect();
+        let chosen = ui.menu(&items, x, y);
+        if self.capturing.is_none() {
+            match chosen {
+                Some(i) if i < A::ALL.len() => {
+                    self.capturing = Some((i, ui.focus()));
+                    self.capture = BindingCapture::new();
+                }
+                Some(_) => {
+                    controls.reset(player);
+                    changed = true;
+                }
+                None => {}
+            }
+        }
+        changed
+    }
+}
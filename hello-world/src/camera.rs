@@ -0,0 +1,201 @@
+use crate::{
+    geometry::{Rect, SCREEN_RECT},
+    rng::Rng,
+    sprite::{Sprite, Texture},
+    tilemap::Tilemap,
+    wasm4::{self, BLIT_ROTATE, SCREEN_SIZE},
+};
+
+/// A view onto a larger world, which converts world coordinates to screen coordinates and skips
+/// drawing anything that falls outside the screen.
+pub struct Camera {
+    // world position of the top-left corner of the screen, before shaking
+    pub x: f32,
+    pub y: f32,
+    // the area of the world the camera is kept inside, if any
+    pub bounds: Option<Rect>,
+    // an area of the screen that a followed target can move around in without moving the camera
+    pub deadzone: Option<Rect>,
+    // how much of the distance to its target the camera covers each frame, from 0 to 1
+    pub follow_speed: f32,
+    shake_magnitude: u32,
+    shake_frames: u32,
+    shake_frames_total: u32,
+    shake_offset: (i32, i32),
+    shake_rng: Rng,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            bounds: None,
+            deadzone: None,
+            follow_speed: 1.0,
+            shake_magnitude: 0,
+            shake_frames: 0,
+            shake_frames_total: 0,
+            shake_offset: (0, 0),
+            shake_rng: Rng::new(0x2545_f491),
+        }
+    }
+
+    /// Moves the camera to center on a point, then clamps it to its bounds.
+    pub fn center_on(&mut self, x: i32, y: i32) {
+        self.x = (x - SCREEN_SIZE as i32 / 2) as f32;
+        self.y = (y - SCREEN_SIZE as i32 / 2) as f32;
+        self.clamp_to_bounds();
+    }
+
+    /// Moves the camera towards a point in the world, keeping it inside the deadzone if there is
+    /// one and centering on it otherwise. Call once per frame.
+    pub fn follow(&mut self, x: i32, y: i32) {
+        let (x, y) = (x as f32, y as f32);
+        let (target_x, target_y) = match self.deadzone {
+            Some(deadzone) => (
+                Self::deadzone_axis(self.x, x, deadzone.x, deadzone.right()),
+                Self::deadzone_axis(self.y, y, deadzone.y, deadzone.bottom()),
+            ),
+            None => (x - (SCREEN_SIZE / 2) as f32, y - (SCREEN_SIZE / 2) as f32),
+        };
+        self.x += (target_x - self.x) * self.follow_speed;
+        self.y += (target_y - self.y) * self.follow_speed;
+        self.clamp_to_bounds();
+    }
+
+    fn deadzone_axis(camera: f32, target: f32, min: i32, max: i32) -> f32 {
+        let on_screen = target - camera;
+        if on_screen < min as f32 {
+            target - min as f32
+        } else if on_screen > max as f32 {
+            target - max as f32
+        } else {
+            camera
+        }
+    }
+
+    fn clamp_to_bounds(&mut self) {
+        if let Some(bounds) = self.bounds {
+            self.x = Self::clamp_axis(self.x, bounds.x, bounds.width);
+            self.y = Self::clamp_axis(self.y, bounds.y, bounds.height);
+        }
+    }
+
+    fn clamp_axis(camera: f32, min: i32, size: u32) -> f32 {
+        if size <= SCREEN_SIZE {
+            // too small to scroll around in, so keep it centered
+            (min - (SCREEN_SIZE - size) as i32 / 2) as f32
+        } else {
+            camera.clamp(min as f32, (min + (size - SCREEN_SIZE) as i32) as f32)
+        }
+    }
+
+    /// Shakes the view by up to `magnitude` pixels, dying down over `frames` frames.
+    pub fn shake(&mut self, magnitude: u32, frames: u32) {
+        self.shake_magnitude = magnitude;
+        self.shake_frames = frames;
+        self.shake_frames_total = frames;
+    }
+
+    /// Advances the screen shake. Call once per frame.
+    pub fn update(&mut self) {
+        if self.shake_frames == 0 {
+            self.shake_offset = (0, 0);
+            return;
+        }
+        let magnitude =
+            (self.shake_magnitude * self.shake_frames).div_ceil(self.shake_frames_total) as i32;
+        self.shake_frames -= 1;
+        self.shake_offset = (
+            self.shake_rng.range(-magnitude, magnitude),
+            self.shake_rng.range(-magnitude, magnitude),
+        );
+    }
+
+    /// The world position of the top-left corner of the screen, including any shake.
+    pub fn position(&self) -> (i32, i32) {
+        (
+            self.x.round() as i32 + self.shake_offset.0,
+            self.y.round() as i32 + self.shake_offset.1,
+        )
+    }
+
+    /// The area of the world currently on screen.
+    pub fn view(&self) -> Rect {
+        let (x, y) = self.position();
+        Rect::new(x, y, SCREEN_SIZE, SCREEN_SIZE)
+    }
+
+    pub fn world_to_screen(&self, x: i32, y: i32) -> (i32, i32) {
+        let (camera_x, camera_y) = self.position();
+        (x - camera_x, y - camera_y)
+    }
+
+    pub fn screen_to_world(&self, x: i32, y: i32) -> (i32, i32) {
+        let (camera_x, camera_y) = self.position();
+        (x + camera_x, y + camera_y)
+    }
+
+    /// Converts an area of the world to the screen, or `None` if none of it would be visible.
+    fn visible(&self, x: i32, y: i32, width: u32, height: u32) -> Option<(i32, i32)> {
+        let (x, y) = self.world_to_screen(x, y);
+        if Rect::new(x, y, width, height).intersects(&SCREEN_RECT) {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    pub fn draw_sprite<T: Texture>(&self, sprite: &Sprite<T>, x: i32, y: i32, flags: u32) {
+        let (width, height) = if flags & BLIT_ROTATE != 0 {
+            (sprite.height, sprite.width)
+        } else {
+            (sprite.width, sprite.height)
+        };
+        if let Some((x, y)) = self.visible(x, y, width, height) {
+            sprite.draw(x, y, flags);
+        }
+    }
+
+    pub fn draw_tilemap<T: Texture>(&self, tilemap: &Tilemap<T>, x: i32, y: i32) {
+        let bounds = tilemap.bounds(x, y);
+        if let Some((x, y)) = self.visible(bounds.x, bounds.y, bounds.width, bounds.height) {
+            tilemap.draw(x, y);
+        }
+    }
+
+    pub fn rect(&self, x: i32, y: i32, width: u32, height: u32) {
+        if let Some((x, y)) = self.visible(x, y, width, height) {
+            wasm4::rect(x, y, width, height);
+        }
+    }
+
+    pub fn oval(&self, x: i32, y: i32, width: u32, height: u32) {
+        if let Some((x, y)) = self.visible(x, y, width, height) {
+            wasm4::oval(x, y, width, height);
+        }
+    }
+
+    pub fn line(&self, x1: i32, y1: i32, x2: i32, y2: i32) {
+        let (x, y) = (x1.min(x2), y1.min(y2));
+        let (width, height) = ((x1 - x2).unsigned_abs() + 1, (y1 - y2).unsigned_abs() + 1);
+        if self.visible(x, y, width, height).is_some() {
+            let (x1, y1) = self.world_to_screen(x1, y1);
+            let (x2, y2) = self.world_to_screen(x2, y2);
+            wasm4::line(x1, y1, x2, y2);
+        }
+    }
+
+    pub fn hline(&self, x: i32, y: i32, len: u32) {
+        if let Some((x, y)) = self.visible(x, y, len, 1) {
+            wasm4::hline(x, y, len);
+        }
+    }
+
+    pub fn vline(&self, x: i32, y: i32, len: u32) {
+        if let Some((x, y)) = self.visible(x, y, 1, len) {
+            wasm4::vline(x, y, len);
+        }
+    }
+}
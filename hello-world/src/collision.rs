@@ -0,0 +1,190 @@
+use crate::{
+    geometry::Rect,
+    sprite::{Sprite, Texture},
+    wasm4::{BLIT_FLIP_X, BLIT_FLIP_Y},
+};
+
+/// Which pixels of a sprite are solid, for pixel-perfect collisions. Masks are built once from a
+/// sprite, with the mirrored version worked out up front, so overlap tests only have to compare
+/// rows 32 pixels at a time. Flip flags are the same `BLIT_FLIP_X` and `BLIT_FLIP_Y` flags the
+/// sprite is drawn with; `BLIT_ROTATE` isn't supported.
+pub struct CollisionMask {
+    width: u32,
+    height: u32,
+    words_per_row: usize,
+    // one bit per pixel, 32 pixels to a word with the leftmost in the highest bit
+    rows: Vec<u32>,
+    rows_flipped_x: Vec<u32>,
+    // the smallest area holding every solid pixel, relative to the unflipped sprite
+    solid_bounds: Option<Rect>,
+}
+
+/// The 32 pixels of a row starting at pixel `start`, with anything off either end of the row
+/// counting as empty.
+fn window(row: &[u32], start: i32) -> u32 {
+    let word = start.div_euclid(32);
+    let shift = start.rem_euclid(32) as u32;
+    let get = |i: i32| {
+        if i < 0 {
+            0
+        } else {
+            row.get(i as usize).copied().unwrap_or(0)
+        }
+    };
+    if shift == 0 {
+        get(word)
+    } else {
+        (get(word) << shift) | (get(word + 1) >> (32 - shift))
+    }
+}
+
+impl CollisionMask {
+    /// Builds a mask from the pixels of a sprite that its `draw_colors` don't leave transparent.
+    pub fn from_sprite<T: Texture>(sprite: &Sprite<T>) -> Self {
+        Self::from_fn(sprite.width, sprite.height, |x, y| {
+            let color = sprite.texture.pixel(sprite.src_x + x, sprite.src_y + y);
+            (sprite.draw_colors >> (color * 4)) & 0xf != 0
+        })
+    }
+
+    /// Builds a mask with the pixels for which `solid` is true set.
+    pub fn from_fn(width: u32, height: u32, solid: impl Fn(u32, u32) -> bool) -> Self {
+        let words_per_row = width.div_ceil(32) as usize;
+        let mut rows = vec![0; words_per_row * height as usize];
+        let mut rows_flipped_x = vec![0; words_per_row * height as usize];
+        let mut solid_bounds: Option<(u32, u32, u32, u32)> = None;
+        for y in 0..height {
+            let row = y as usize * words_per_row;
+            for x in 0..width {
+                if !solid(x, y) {
+                    continue;
+                }
+                rows[row + (x / 32) as usize] |= 0x8000_0000 >> (x % 32);
+                let flipped_x = width - 1 - x;
+                rows_flipped_x[row + (flipped_x / 32) as usize] |= 0x8000_0000 >> (flipped_x % 32);
+                solid_bounds = Some(match solid_bounds {
+                    None => (x, y, x, y),
+                    Some((left, top, right, bottom)) => {
+                        (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                    }
+                });
+            }
+        }
+        Self {
+            width,
+            height,
+            words_per_row,
+            rows,
+            rows_flipped_x,
+            solid_bounds: solid_bounds.map(|(left, top, right, bottom)| {
+                Rect::new(left as i32, top as i32, right - left + 1, bottom - top + 1)
+            }),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The row of the mask shown `y` pixels from the top when drawn with `flags`.
+    fn row(&self, y: u32, flags: u32) -> &[u32] {
+        let y = if flags & BLIT_FLIP_Y != 0 {
+            self.height - 1 - y
+        } else {
+            y
+        };
+        let rows = if flags & BLIT_FLIP_X != 0 {
+            &self.rows_flipped_x
+        } else {
+            &self.rows
+        };
+        let start = y as usize * self.words_per_row;
+        &rows[start..start + self.words_per_row]
+    }
+
+    /// The area holding every solid pixel when the mask is drawn at `x`, `y` with `flags`.
+    pub fn solid_bounds(&self, x: i32, y: i32, flags: u32) -> Option<Rect> {
+        let mut bounds = self.solid_bounds?;
+        if flags & BLIT_FLIP_X != 0 {
+            bounds.x = self.width as i32 - bounds.right();
+        }
+        if flags & BLIT_FLIP_Y != 0 {
+            bounds.y = self.height as i32 - bounds.bottom();
+        }
+        bounds.x += x;
+        bounds.y += y;
+        Some(bounds)
+    }
+
+    /// Whether the pixel at `x`, `y` of the mask is solid when drawn with `flags`.
+    pub fn is_solid(&self, x: i32, y: i32, flags: u32) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return false;
+        }
+        window(self.row(y as u32, flags), x) & 0x8000_0000 != 0
+    }
+
+    /// Whether any solid pixels overlap between this mask drawn at `x`, `y` with `flags` and
+    /// `other` drawn at `other_x`, `other_y` with `other_flags`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn overlaps(
+        &self,
+        x: i32,
+        y: i32,
+        flags: u32,
+        other: &CollisionMask,
+        other_x: i32,
+        other_y: i32,
+        other_flags: u32,
+    ) -> bool {
+        let Some(area) = self.solid_bounds(x, y, flags).and_then(|bounds| {
+            other
+                .solid_bounds(other_x, other_y, other_flags)
+                .and_then(|other_bounds| bounds.intersection(&other_bounds))
+        }) else {
+            return false;
+        };
+        for screen_y in area.y..area.bottom() {
+            let row = self.row((screen_y - y) as u32, flags);
+            let other_row = other.row((screen_y - other_y) as u32, other_flags);
+            for start in (area.x..area.right()).step_by(32) {
+                let bits = window(row, start - x) & window(other_row, start - other_x);
+                if bits & span_mask(start, area.right()) != 0 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Whether any solid pixels of this mask drawn at `x`, `y` with `flags` fall inside `rect`.
+    pub fn overlaps_rect(&self, x: i32, y: i32, flags: u32, rect: &Rect) -> bool {
+        let Some(area) = self
+            .solid_bounds(x, y, flags)
+            .and_then(|bounds| bounds.intersection(rect))
+        else {
+            return false;
+        };
+        for screen_y in area.y..area.bottom() {
+            let row = self.row((screen_y - y) as u32, flags);
+            for start in (area.x..area.right()).step_by(32) {
+                if window(row, start - x) & span_mask(start, area.right()) != 0 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+/// The bits of a 32 pixel window starting at `start` that come before `end`.
+fn span_mask(start: i32, end: i32) -> u32 {
+    match end - start {
+        32.. => u32::MAX,
+        len => !(u32::MAX >> len),
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::OnceLock;
+
+use crate::{
+    rle::rle_decode,
+    sprite::{Texture, Texture2Color, Texture4Color},
+    wasm4::{BLIT_1BPP, BLIT_2BPP},
+};
+
+/// Run-length encoded bytes, unpacked onto the heap the first time they're needed and kept there.
+struct CompressedData {
+    data: &'static [u8],
+    decoded_len: usize,
+    decoded: OnceLock<Vec<u8>>,
+}
+
+impl CompressedData {
+    const fn new(data: &'static [u8], decoded_len: usize) -> Self {
+        Self {
+            data,
+            decoded_len,
+            decoded: OnceLock::new(),
+        }
+    }
+
+    fn get(&self) -> &[u8] {
+        self.decoded.get_or_init(|| {
+            let mut decoded = vec![0; self.decoded_len];
+            rle_decode(self.data, &mut decoded);
+            decoded
+        })
+    }
+
+    fn decode_into<'b>(&self, buf: &'b mut [u8]) -> &'b [u8] {
+        let len = rle_decode(self.data, buf);
+        &buf[..len]
+    }
+}
+
+/// A `Texture2Color` stored run-length encoded, generated by `build.rs`. `texture` unpacks it onto
+/// the heap the first time it's used, and `decode_into` unpacks it into a buffer of the caller's
+/// instead, such as a static one.
+pub struct CompressedTexture2Color {
+    data: CompressedData,
+    stride: u32,
+    height: u32,
+}
+
+impl CompressedTexture2Color {
+    pub const fn new(data: &'static [u8], decoded_len: usize, stride: u32, height: u32) -> Self {
+        Self {
+            data: CompressedData::new(data, decoded_len),
+            stride,
+            height,
+        }
+    }
+
+    /// The texture, unpacked onto the heap the first time this is called.
+    pub fn texture(&self) -> Texture2Color<'_> {
+        Texture2Color {
+            data: self.data.get(),
+            stride: self.stride,
+            height: self.height,
+        }
+    }
+
+    /// Unpacks the texture into `buf`, which must hold at least `decoded_len` bytes.
+    pub fn decode_into<'b>(&self, buf: &'b mut [u8]) -> Texture2Color<'b> {
+        Texture2Color {
+            data: self.data.decode_into(buf),
+            stride: self.stride,
+            height: self.height,
+        }
+    }
+
+    pub fn decoded_len(&self) -> usize {
+        self.data.decoded_len
+    }
+}
+
+// unpacks the texture the first time it's drawn, so a compressed texture can be used directly,
+// such as in a `SpriteFont`
+impl Texture for CompressedTexture2Color {
+    const BLIT_FORMAT: u32 = BLIT_1BPP;
+
+    fn data(&self) -> &[u8] {
+        self.data.get()
+    }
+
+    fn stride(&self) -> u32 {
+        self.stride
+    }
+}
+
+/// A `Texture4Color` stored run-length encoded, like `CompressedTexture2Color`.
+pub struct CompressedTexture4Color {
+    data: CompressedData,
+    stride: u32,
+    height: u32,
+}
+
+impl CompressedTexture4Color {
+    pub const fn new(data: &'static [u8], decoded_len: usize, stride: u32, height: u32) -> Self {
+        Self {
+            data: CompressedData::new(data, decoded_len),
+            stride,
+            height,
+        }
+    }
+
+    /// The texture, unpacked onto the heap the first time this is called.
+    pub fn texture(&self) -> Texture4Color<'_> {
+        Texture4Color {
+            data: self.data.get(),
+            stride: self.stride,
+            height: self.height,
+        }
+    }
+
+    /// Unpacks the texture into `buf`, which must hold at least `decoded_len` bytes.
+    pub fn decode_into<'b>(&self, buf: &'b mut [u8]) -> Texture4Color<'b> {
+        Texture4Color {
+            data: self.data.decode_into(buf),
+            stride: self.stride,
+            height: self.height,
+        }
+    }
+
+    pub fn decoded_len(&self) -> usize {
+        self.data.decoded_len
+    }
+}
+
+impl Texture for CompressedTexture4Color {
+    const BLIT_FORMAT: u32 = BLIT_2BPP;
+
+    fn data(&self) -> &[u8] {
+        self.data.get()
+    }
+
+    fn stride(&self) -> u32 {
+        self.stride
+    }
+}
@@ -0,0 +1,222 @@
+use std::borrow::Cow;
+
+use crate::{
+    font::Font,
+    sprite::{Sprite, Texture},
+    wasm4, wasm4_mmio,
+};
+
+pub enum DrawOp<'a> {
+    Blit {
+        data: &'a [u8],
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        src_x: u32,
+        src_y: u32,
+        stride: u32,
+        flags: u32,
+    },
+    Text {
+        font: &'a (dyn Font + Sync),
+        text: Cow<'a, str>,
+        x: i32,
+        y: i32,
+    },
+    Rect {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+    Oval {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+    Line {
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+    },
+    HLine {
+        x: i32,
+        y: i32,
+        len: u32,
+    },
+    VLine {
+        x: i32,
+        y: i32,
+        len: u32,
+    },
+}
+
+impl DrawOp<'_> {
+    fn draw(&self) {
+        match self {
+            DrawOp::Blit {
+                data,
+                x,
+                y,
+                width,
+                height,
+                src_x,
+                src_y,
+                stride,
+                flags,
+            } => wasm4::blit_sub(
+                data, *x, *y, *width, *height, *src_x, *src_y, *stride, *flags,
+            ),
+            DrawOp::Text { font, text, x, y } => font.blit_string(text, *x, *y),
+            DrawOp::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => wasm4::rect(*x, *y, *width, *height),
+            DrawOp::Oval {
+                x,
+                y,
+                width,
+                height,
+            } => wasm4::oval(*x, *y, *width, *height),
+            DrawOp::Line { x1, y1, x2, y2 } => wasm4::line(*x1, *y1, *x2, *y2),
+            DrawOp::HLine { x, y, len } => wasm4::hline(*x, *y, *len),
+            DrawOp::VLine { x, y, len } => wasm4::vline(*x, *y, *len),
+        }
+    }
+}
+
+pub struct DrawCommand<'a> {
+    pub z: i32,
+    pub draw_colors: u16,
+    pub op: DrawOp<'a>,
+}
+
+/// Collects drawing commands over a frame so that they can be drawn in order of their z values,
+/// lowest first, with `DRAW_COLORS` only written when it needs to change.
+pub struct DrawList<'a> {
+    commands: Vec<DrawCommand<'a>>,
+}
+
+impl<'a> DrawList<'a> {
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn push(&mut self, z: i32, draw_colors: u16, op: DrawOp<'a>) {
+        self.commands.push(DrawCommand { z, draw_colors, op });
+    }
+
+    pub fn sprite<T: Texture>(
+        &mut self,
+        z: i32,
+        sprite: &Sprite<'a, T>,
+        x: i32,
+        y: i32,
+        flags: u32,
+    ) {
+        self.push(
+            z,
+            sprite.draw_colors,
+            DrawOp::Blit {
+                data: sprite.texture.data(),
+                x,
+                y,
+                width: sprite.width,
+                height: sprite.height,
+                src_x: sprite.src_x,
+                src_y: sprite.src_y,
+                stride: sprite.texture.stride(),
+                flags: flags | T::BLIT_FORMAT,
+            },
+        );
+    }
+
+    /// Queues `text` in `font`, which can be a `&'static str` or a `String` made this frame.
+    pub fn text(
+        &mut self,
+        z: i32,
+        font: &'a (dyn Font + Sync),
+        text: impl Into<Cow<'a, str>>,
+        x: i32,
+        y: i32,
+    ) {
+        self.push(
+            z,
+            font.draw_colors(),
+            DrawOp::Text {
+                font,
+                text: text.into(),
+                x,
+                y,
+            },
+        );
+    }
+
+    pub fn rect(&mut self, z: i32, draw_colors: u16, x: i32, y: i32, width: u32, height: u32) {
+        self.push(
+            z,
+            draw_colors,
+            DrawOp::Rect {
+                x,
+                y,
+                width,
+                height,
+            },
+        );
+    }
+
+    pub fn oval(&mut self, z: i32, draw_colors: u16, x: i32, y: i32, width: u32, height: u32) {
+        self.push(
+            z,
+            draw_colors,
+            DrawOp::Oval {
+                x,
+                y,
+                width,
+                height,
+            },
+        );
+    }
+
+    pub fn line(&mut self, z: i32, draw_colors: u16, x1: i32, y1: i32, x2: i32, y2: i32) {
+        self.push(z, draw_colors, DrawOp::Line { x1, y1, x2, y2 });
+    }
+
+    pub fn hline(&mut self, z: i32, draw_colors: u16, x: i32, y: i32, len: u32) {
+        self.push(z, draw_colors, DrawOp::HLine { x, y, len });
+    }
+
+    pub fn vline(&mut self, z: i32, draw_colors: u16, x: i32, y: i32, len: u32) {
+        self.push(z, draw_colors, DrawOp::VLine { x, y, len });
+    }
+
+    /// Draws everything in the list and empties it. Commands with the same z value are drawn in
+    /// the order they were added, so later ones stay on top. Call at the end of `update()`.
+    pub fn flush(&mut self) {
+        // a stable sort, so commands with the same z keep their order
+        self.commands.sort_by_key(|command| command.z);
+        let mut draw_colors = None;
+        for command in self.commands.drain(..) {
+            if draw_colors != Some(command.draw_colors) {
+                wasm4_mmio::DRAW_COLORS.write(command.draw_colors);
+                draw_colors = Some(command.draw_colors);
+            }
+            command.op.draw();
+        }
+    }
+}
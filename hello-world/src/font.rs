@@ -0,0 +1,319 @@
+use crate::{
+    geometry::Rect,
+    markup::{self, MarkupState, MarkupStyle, Tag, Token, Tokens},
+    sprite::Texture,
+    text::{self, HorizontalAlign, TextItem, TextItemKind, TextLayout, VerticalAlign},
+    wasm4, wasm4_mmio,
+};
+
+/// The location and width of a single character within a font texture.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Glyph {
+    pub src_x: u32,
+    pub src_y: u32,
+    pub width: u32,
+}
+
+/// A run of consecutive characters whose glyphs are stored consecutively, starting at
+/// `glyph_index` in the font's glyph table.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CharRange {
+    pub first: char,
+    pub len: u32,
+    pub glyph_index: u32,
+}
+
+impl CharRange {
+    pub fn glyph_index(&self, c: char) -> Option<u32> {
+        let offset = (c as u32).wrapping_sub(self.first as u32);
+        if offset < self.len {
+            Some(self.glyph_index + offset)
+        } else {
+            None
+        }
+    }
+}
+
+/// An adjustment to the spacing between two specific characters drawn next to each other.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KerningPair {
+    pub left: char,
+    pub right: char,
+    pub adjust: i32,
+}
+
+pub struct SpriteFont<'a, T: Texture> {
+    pub texture: &'a T,
+    pub glyphs: &'a [Glyph],
+    // must be sorted by `first`, and not overlap
+    pub char_map: &'a [CharRange],
+    // drawn in place of characters that aren't in `char_map`
+    pub fallback: char,
+    pub glyph_height: u32,
+    pub kerning: &'a [KerningPair],
+    pub horizontal_padding: u32,
+    // extra space between lines of text
+    pub line_spacing: u32,
+    pub draw_colors: u16,
+}
+
+impl<'a, T: Texture> SpriteFont<'a, T> {
+    pub fn glyph_index(&self, c: char) -> Option<u32> {
+        let i = self.char_map.partition_point(|range| range.first <= c);
+        self.char_map[..i].last()?.glyph_index(c)
+    }
+
+    pub fn glyph(&self, c: char) -> &Glyph {
+        let i = self
+            .glyph_index(c)
+            .or_else(|| self.glyph_index(self.fallback))
+            .unwrap_or(0);
+        &self.glyphs[i as usize]
+    }
+
+    pub fn kerning(&self, left: char, right: char) -> i32 {
+        self.kerning
+            .iter()
+            .find(|pair| pair.left == left && pair.right == right)
+            .map_or(0, |pair| pair.adjust)
+    }
+
+    /// The distance from the start of `c` to the start of the character after it.
+    pub fn advance(&self, c: char, next: Option<char>) -> i32 {
+        let kerning = next.map_or(0, |next| self.kerning(c, next));
+        (self.glyph(c).width + self.horizontal_padding) as i32 + kerning
+    }
+
+    /// Splits plain text into items for layout.
+    pub fn text_items(&self, s: &str) -> Vec<TextItem> {
+        let mut items = Vec::new();
+        let mut chars = s.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            let next = chars.peek().map(|&(_, next)| next);
+            let (kind, width, advance) = match c {
+                '\n' => (TextItemKind::Newline, 0, 0),
+                ' ' => (
+                    TextItemKind::Space,
+                    self.glyph(c).width,
+                    self.advance(c, next),
+                ),
+                _ => (
+                    TextItemKind::Glyph,
+                    self.glyph(c).width,
+                    self.advance(c, next),
+                ),
+            };
+            items.push(TextItem {
+                start,
+                end: start + c.len_utf8(),
+                kind,
+                width,
+                advance,
+            });
+        }
+        items
+    }
+
+    /// The width of a single line of text, ignoring any line breaks.
+    pub fn measure_line(&self, s: &str) -> u32 {
+        text::line_width(&self.text_items(s))
+    }
+
+    /// The width and height of the box that `draw_string` would fill when drawing `s`.
+    pub fn measure(&self, s: &str) -> (u32, u32) {
+        let mut width = 0;
+        let mut line_count = 0;
+        for line in s.split('\n') {
+            width = width.max(self.measure_line(line));
+            line_count += 1;
+        }
+        (width, self.lines_height(line_count))
+    }
+
+    /// The width and height of the box that `draw_markup` would fill when drawing `s`.
+    pub fn measure_markup(&self, s: &str, style: &MarkupStyle<T>) -> (u32, u32) {
+        let items = markup::items(self, style, s);
+        let lines = text::wrap(&items, None);
+        let width = lines
+            .iter()
+            .map(|line| text::line_width(&items[line.clone()]))
+            .max()
+            .unwrap_or(0);
+        (width, self.lines_height(lines.len() as u32))
+    }
+
+    /// Where each of `lines` should be drawn to be placed inside `rect` according to `layout`.
+    fn place_lines(
+        &self,
+        line_widths: impl ExactSizeIterator<Item = u32>,
+        rect: Rect,
+        layout: &TextLayout,
+    ) -> Vec<(i32, i32)> {
+        let text_height = self.lines_height(line_widths.len() as u32) as i32;
+        let mut y = match layout.vertical_align {
+            VerticalAlign::Top => rect.y,
+            VerticalAlign::Middle => rect.y + (rect.height as i32 - text_height) / 2,
+            VerticalAlign::Bottom => rect.bottom() - text_height,
+        };
+        line_widths
+            .map(|line_width| {
+                let x = match layout.horizontal_align {
+                    HorizontalAlign::Left => rect.x,
+                    HorizontalAlign::Center => rect.x + (rect.width as i32 - line_width as i32) / 2,
+                    HorizontalAlign::Right => rect.right() - line_width as i32,
+                };
+                let origin = (x, y);
+                y += (self.glyph_height + self.line_spacing) as i32;
+                origin
+            })
+            .collect()
+    }
+
+    /// The height taken up by `line_count` lines of text.
+    pub fn lines_height(&self, line_count: u32) -> u32 {
+        match line_count {
+            0 => 0,
+            n => n * self.glyph_height + (n - 1) * self.line_spacing,
+        }
+    }
+
+    fn draw_glyph(&self, c: char, x: i32, y: i32) {
+        let glyph = self.glyph(c);
+        wasm4::blit_sub(
+            self.texture.data(),
+            x,
+            y,
+            glyph.width,
+            self.glyph_height,
+            glyph.src_x,
+            glyph.src_y,
+            self.texture.stride(),
+            T::BLIT_FORMAT,
+        );
+    }
+
+    pub fn draw_string(&self, s: &str, x: i32, y: i32) {
+        wasm4_mmio::DRAW_COLORS.write(self.draw_colors);
+        self.blit_string(s, x, y);
+    }
+
+    /// Draws `s` like `draw_string`, but with whatever `DRAW_COLORS` is already set to.
+    pub fn blit_string(&self, s: &str, x: i32, y: i32) {
+        let line_x = x;
+        let mut x = x;
+        let mut y = y;
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '\n' {
+                x = line_x;
+                y += (self.glyph_height + self.line_spacing) as i32;
+                continue;
+            }
+            if c != ' ' {
+                self.draw_glyph(c, x, y);
+            }
+            x += self.advance(c, chars.peek().copied());
+        }
+    }
+
+    /// Draws `s` inside `rect`, wrapping, aligning and truncating it according to `layout`.
+    pub fn draw_text(&self, s: &str, rect: Rect, layout: &TextLayout) {
+        let items = self.text_items(s);
+        let lines = text::layout_lines(self, s, &items, rect.width, rect.height, layout);
+        let line_widths = lines.iter().map(|line| self.measure_line(line));
+        for (line, (x, y)) in lines
+            .iter()
+            .zip(self.place_lines(line_widths, rect, layout))
+        {
+            self.draw_string(line, x, y);
+        }
+    }
+
+    fn draw_markup_line(
+        &self,
+        s: &str,
+        x: i32,
+        y: i32,
+        style: &MarkupStyle<T>,
+        state: &mut MarkupState,
+    ) {
+        let items = markup::items(self, style, s);
+        let mut x = x;
+        for (item, (_, _, token)) in items.iter().zip(Tokens::new(s)) {
+            match token {
+                Token::Char(' ') => {}
+                Token::Char(c) => {
+                    let (dx, dy) = state.next_offset(style.frame);
+                    self.draw_glyph(c, x + dx, y + dy);
+                }
+                Token::Tag(Tag::Icon(name)) => {
+                    if let Some(sprite) = style.icon(name) {
+                        let (dx, dy) = state.next_offset(style.frame);
+                        let icon_y = y + (self.glyph_height as i32 - sprite.height as i32) / 2;
+                        sprite.draw(x + dx, icon_y + dy, 0);
+                        wasm4_mmio::DRAW_COLORS.write(state.draw_colors());
+                    }
+                }
+                Token::Tag(tag) => {
+                    if state.apply(&tag) {
+                        wasm4_mmio::DRAW_COLORS.write(state.draw_colors());
+                    }
+                }
+            }
+            x += item.advance;
+        }
+    }
+
+    /// Draws markup (see the `markup` module) starting at a point, breaking lines only at `\n`.
+    pub fn draw_markup(&self, s: &str, x: i32, y: i32, style: &MarkupStyle<T>) {
+        let mut state = MarkupState::new(self.draw_colors);
+        wasm4_mmio::DRAW_COLORS.write(state.draw_colors());
+        let mut y = y;
+        for line in s.split('\n') {
+            self.draw_markup_line(line, x, y, style, &mut state);
+            y += (self.glyph_height + self.line_spacing) as i32;
+        }
+    }
+
+    /// Draws markup inside `rect`, wrapping, aligning and truncating it according to `layout`.
+    pub fn draw_markup_text(
+        &self,
+        s: &str,
+        rect: Rect,
+        layout: &TextLayout,
+        style: &MarkupStyle<T>,
+    ) {
+        let items = markup::items(self, style, s);
+        let lines = text::layout_lines(self, s, &items, rect.width, rect.height, layout);
+        let line_widths = lines
+            .iter()
+            .map(|line| text::line_width(&markup::items(self, style, line)));
+        let mut state = MarkupState::new(self.draw_colors);
+        wasm4_mmio::DRAW_COLORS.write(state.draw_colors());
+        for (line, (x, y)) in lines
+            .iter()
+            .zip(self.place_lines(line_widths, rect, layout))
+        {
+            self.draw_markup_line(line, x, y, style, &mut state);
+        }
+    }
+}
+
+/// A font of any texture format, so fonts of different formats can be kept together, such as in a
+/// `DrawList`.
+pub trait Font {
+    fn draw_colors(&self) -> u16;
+
+    /// Draws `s` with its top-left corner at `x`, `y`, with whatever `DRAW_COLORS` is set to.
+    fn blit_string(&self, s: &str, x: i32, y: i32);
+}
+
+impl<T: Texture> Font for SpriteFont<'_, T> {
+    fn draw_colors(&self) -> u16 {
+        self.draw_colors
+    }
+
+    fn blit_string(&self, s: &str, x: i32, y: i32) {
+        SpriteFont::blit_string(self, s, x, y);
+    }
+}
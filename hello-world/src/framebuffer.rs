@@ -0,0 +1,334 @@
+use voladdress::{Safe, VolBlock};
+
+use crate::{wasm4::SCREEN_SIZE, wasm4_mmio::FRAMEBUFFER};
+
+/// Something that can be drawn on a pixel at a time, using palette indices 0 to 3 as colors.
+pub trait Surface {
+    fn width(&self) -> u32;
+    fn height(&self) -> u32;
+
+    /// The color at a point, or `None` if it's outside the surface.
+    fn get_pixel(&self, x: i32, y: i32) -> Option<u8>;
+
+    /// Sets the color at a point, doing nothing if it's outside the surface.
+    fn set_pixel(&mut self, x: i32, y: i32, color: u8);
+
+    fn hline(&mut self, x: i32, y: i32, len: u32, color: u8) {
+        for x in x.max(0)..(x + len as i32).min(self.width() as i32) {
+            self.set_pixel(x, y, color);
+        }
+    }
+
+    fn vline(&mut self, x: i32, y: i32, len: u32, color: u8) {
+        for y in y.max(0)..(y + len as i32).min(self.height() as i32) {
+            self.set_pixel(x, y, color);
+        }
+    }
+
+    fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: u8) {
+        for y in y.max(0)..(y + height as i32).min(self.height() as i32) {
+            self.hline(x, y, width, color);
+        }
+    }
+
+    fn clear(&mut self, color: u8) {
+        self.fill_rect(0, 0, self.width(), self.height(), color);
+    }
+}
+
+/// Byte storage for a `Framebuffer`.
+pub trait PixelBytes {
+    fn read(&self, i: usize) -> u8;
+    fn write(&mut self, i: usize, value: u8);
+}
+
+impl<const N: usize> PixelBytes for VolBlock<u8, Safe, Safe, N> {
+    fn read(&self, i: usize) -> u8 {
+        self.index(i).read()
+    }
+
+    fn write(&mut self, i: usize, value: u8) {
+        self.index(i).write(value)
+    }
+}
+
+impl<const N: usize> PixelBytes for [u8; N] {
+    fn read(&self, i: usize) -> u8 {
+        self[i]
+    }
+
+    fn write(&mut self, i: usize, value: u8) {
+        self[i] = value
+    }
+}
+
+impl PixelBytes for Vec<u8> {
+    fn read(&self, i: usize) -> u8 {
+        self[i]
+    }
+
+    fn write(&mut self, i: usize, value: u8) {
+        self[i] = value
+    }
+}
+
+/// Pixels stored the way WASM-4 lays out its framebuffer: 2 bits per pixel, 4 pixels per byte,
+/// with the leftmost pixel in the lowest bits, and rows following each other with no padding.
+pub struct Framebuffer<B: PixelBytes> {
+    bytes: B,
+    width: u32,
+    height: u32,
+}
+
+pub type ScreenFramebuffer = Framebuffer<VolBlock<u8, Safe, Safe, 6400>>;
+
+impl ScreenFramebuffer {
+    /// The framebuffer that WASM-4 draws to the screen.
+    pub const fn screen() -> Self {
+        Self {
+            bytes: FRAMEBUFFER,
+            width: SCREEN_SIZE,
+            height: SCREEN_SIZE,
+        }
+    }
+}
+
+// every pixel in a byte set to the same color
+const fn fill_byte(color: u8) -> u8 {
+    (color & 0b11) * 0b0101_0101
+}
+
+/// Which pixel `x`, `y` is in a `width` by `height` image stored a row at a time with no padding,
+/// counting from the top-left, or `None` if it's outside the image.
+pub(crate) fn pixel_index(width: u32, height: u32, x: i32, y: i32) -> Option<usize> {
+    if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+        None
+    } else {
+        Some((y as u32 * width + x as u32) as usize)
+    }
+}
+
+impl<B: PixelBytes> Framebuffer<B> {
+    /// Wraps `bytes`, which must have room for `width * height` pixels.
+    pub fn new(bytes: B, width: u32, height: u32) -> Self {
+        Self {
+            bytes,
+            width,
+            height,
+        }
+    }
+
+    pub fn bytes(&self) -> &B {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> B {
+        self.bytes
+    }
+
+    /// Copies row `src_y` over row `dst_y`.
+    pub fn copy_row(&mut self, src_y: u32, dst_y: u32) {
+        if src_y >= self.height || dst_y >= self.height {
+            return;
+        }
+        if self.width.is_multiple_of(4) {
+            let row_bytes = (self.width / 4) as usize;
+            let (src, dst) = (src_y as usize * row_bytes, dst_y as usize * row_bytes);
+            for i in 0..row_bytes {
+                let value = self.bytes.read(src + i);
+                self.bytes.write(dst + i, value);
+            }
+        } else {
+            for x in 0..self.width as i32 {
+                let color = self.get_pixel(x, src_y as i32).unwrap();
+                self.set_pixel(x, dst_y as i32, color);
+            }
+        }
+    }
+
+    /// Copies column `src_x` over column `dst_x`.
+    pub fn copy_column(&mut self, src_x: u32, dst_x: u32) {
+        if src_x >= self.width || dst_x >= self.width {
+            return;
+        }
+        for y in 0..self.height as i32 {
+            let color = self.get_pixel(src_x as i32, y).unwrap();
+            self.set_pixel(dst_x as i32, y, color);
+        }
+    }
+
+    /// Reads the colors of row `y` into `colors`, starting from column 0.
+    pub fn read_row(&self, y: u32, colors: &mut [u8]) {
+        for (x, color) in colors.iter_mut().take(self.width as usize).enumerate() {
+            *color = self.get_pixel(x as i32, y as i32).unwrap_or(0);
+        }
+    }
+
+    /// Sets the colors of row `y` from `colors`, starting from column 0.
+    pub fn write_row(&mut self, y: u32, colors: &[u8]) {
+        for (x, &color) in colors.iter().take(self.width as usize).enumerate() {
+            self.set_pixel(x as i32, y as i32, color);
+        }
+    }
+}
+
+impl<B: PixelBytes> Surface for Framebuffer<B> {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn get_pixel(&self, x: i32, y: i32) -> Option<u8> {
+        let i = pixel_index(self.width, self.height, x, y)?;
+        Some((self.bytes.read(i / 4) >> ((i % 4) * 2)) & 0b11)
+    }
+
+    fn set_pixel(&mut self, x: i32, y: i32, color: u8) {
+        if let Some(i) = pixel_index(self.width, self.height, x, y) {
+            let shift = (i % 4) * 2;
+            let byte = self.bytes.read(i / 4);
+            let byte = (byte & !(0b11 << shift)) | ((color & 0b11) << shift);
+            self.bytes.write(i / 4, byte);
+        }
+    }
+
+    fn hline(&mut self, x: i32, y: i32, len: u32, color: u8) {
+        if y < 0 || y >= self.height as i32 {
+            return;
+        }
+        let start = x.max(0);
+        let end = (x + len as i32).min(self.width as i32);
+        if start >= end {
+            return;
+        }
+        // set the pixels one at a time up to a byte boundary, then whole bytes at a time
+        let row = y as usize * self.width as usize;
+        let (mut i, end) = (row + start as usize, row + end as usize);
+        while i < end && (i % 4 != 0 || end - i < 4) {
+            self.set_pixel((i - row) as i32, y, color);
+            i += 1;
+        }
+        while end - i >= 4 {
+            self.bytes.write(i / 4, fill_byte(color));
+            i += 4;
+        }
+        while i < end {
+            self.set_pixel((i - row) as i32, y, color);
+            i += 1;
+        }
+    }
+
+    fn clear(&mut self, color: u8) {
+        for i in 0..((self.width * self.height) as usize).div_ceil(4) {
+            self.bytes.write(i, fill_byte(color));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn framebuffer() -> Framebuffer<[u8; 6400]> {
+        Framebuffer::new([0; 6400], SCREEN_SIZE, SCREEN_SIZE)
+    }
+
+    #[test]
+    fn pixels_are_packed_leftmost_in_the_lowest_bits() {
+        let mut fb = framebuffer();
+        for (x, color) in [(0, 1), (1, 2), (2, 3), (3, 1)] {
+            fb.set_pixel(x, 0, color);
+        }
+        assert_eq!(fb.bytes()[0], 0b01_11_10_01);
+        for (x, color) in [(0, 1), (1, 2), (2, 3), (3, 1)] {
+            assert_eq!(fb.get_pixel(x, 0), Some(color));
+        }
+    }
+
+    #[test]
+    fn set_pixel_leaves_the_rest_of_the_byte_alone() {
+        let mut fb = framebuffer();
+        fb.clear(3);
+        for (x, shift) in [(4, 0), (5, 2), (6, 4), (7, 6)] {
+            fb.set_pixel(x, 1, 0);
+            assert_eq!(fb.bytes()[41], !(0b11 << shift));
+            fb.set_pixel(x, 1, 3);
+        }
+        assert_eq!(fb.bytes()[41], 0xff);
+    }
+
+    #[test]
+    fn pixels_outside_are_ignored() {
+        let mut fb = framebuffer();
+        fb.set_pixel(-1, 0, 3);
+        fb.set_pixel(160, 0, 3);
+        fb.set_pixel(0, 160, 3);
+        assert!(fb.bytes().iter().all(|&byte| byte == 0));
+        assert_eq!(fb.get_pixel(-1, 0), None);
+        assert_eq!(fb.get_pixel(0, 160), None);
+    }
+
+    #[test]
+    fn hline_starting_and_ending_mid_byte() {
+        let mut fb = framebuffer();
+        fb.hline(3, 2, 10, 2);
+        let row = &fb.bytes()[80..84];
+        assert_eq!(row, [0b10_00_00_00, 0xaa, 0xaa, 0b00_00_00_10]);
+        for x in 0..16 {
+            let expected = if (3..13).contains(&x) { 2 } else { 0 };
+            assert_eq!(fb.get_pixel(x, 2), Some(expected), "x = {x}");
+        }
+    }
+
+    #[test]
+    fn hline_within_one_byte_and_clipped() {
+        let mut fb = framebuffer();
+        fb.hline(1, 0, 2, 1);
+        assert_eq!(fb.bytes()[0], 0b00_01_01_00);
+        fb.hline(-2, 1, 4, 3);
+        assert_eq!(fb.bytes()[40], 0b00_00_11_11);
+        fb.hline(158, 2, 10, 3);
+        assert_eq!(fb.bytes()[119], 0b11_11_00_00);
+        assert_eq!(fb.bytes()[120], 0);
+    }
+
+    #[test]
+    fn clear_fills_every_byte() {
+        let mut fb = framebuffer();
+        fb.clear(2);
+        assert!(fb.bytes().iter().all(|&byte| byte == 0xaa));
+        assert_eq!(fb.get_pixel(159, 159), Some(2));
+    }
+
+    #[test]
+    fn copy_row_and_column() {
+        let mut fb = framebuffer();
+        fb.set_pixel(0, 5, 1);
+        fb.set_pixel(7, 5, 2);
+        fb.set_pixel(159, 5, 3);
+        fb.copy_row(5, 9);
+        assert_eq!(fb.bytes()[9 * 40..10 * 40], fb.bytes()[5 * 40..6 * 40]);
+
+        fb.copy_column(7, 2);
+        assert_eq!(fb.get_pixel(2, 5), Some(2));
+        assert_eq!(fb.get_pixel(2, 9), Some(2));
+        assert_eq!(fb.get_pixel(2, 0), Some(0));
+        assert_eq!(fb.get_pixel(1, 5), Some(0));
+        assert_eq!(fb.get_pixel(3, 5), Some(0));
+    }
+
+    #[test]
+    fn copy_row_with_a_width_that_isnt_whole_bytes() {
+        let mut fb = Framebuffer::new([0; 5], 5, 4);
+        fb.hline(0, 1, 5, 3);
+        fb.copy_row(1, 3);
+        for x in 0..5 {
+            assert_eq!(fb.get_pixel(x, 3), Some(3));
+        }
+        assert_eq!(fb.get_pixel(0, 2), Some(0));
+        assert_eq!(fb.get_pixel(4, 0), Some(0));
+    }
+}
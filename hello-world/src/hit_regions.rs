@@ -0,0 +1,125 @@
+use crate::{
+    geometry::Rect,
+    input::{Drag, Mouse, MouseButton},
+};
+
+fn index(button: MouseButton) -> usize {
+    button.bit().trailing_zeros() as usize
+}
+
+/// Areas of the screen registered each frame under ids of the game's choosing, so sprites and UI
+/// elements can tell whether the pointer is over them, clicking them or dragging them. Regions
+/// added later are on top of earlier ones. Each frame, call `begin`, `add` every region and then
+/// `update`, after which the results can be read until the next `begin`.
+pub struct HitRegions<Id: Copy + PartialEq> {
+    regions: Vec<(Id, Rect)>,
+    mouse: Mouse,
+    hovered: Option<Id>,
+    // the region under the pointer when each button last went down, indexed by bit position
+    pressed_on: [Option<Id>; 3],
+}
+
+impl<Id: Copy + PartialEq> Default for HitRegions<Id> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Id: Copy + PartialEq> HitRegions<Id> {
+    pub fn new() -> Self {
+        Self {
+            regions: Vec::new(),
+            mouse: Mouse::default(),
+            hovered: None,
+            pressed_on: [None; 3],
+        }
+    }
+
+    /// Forgets last frame's regions, ready for this frame's to be added.
+    pub fn begin(&mut self) {
+        self.regions.clear();
+    }
+
+    pub fn add(&mut self, id: Id, rect: Rect) {
+        self.regions.push((id, rect));
+    }
+
+    /// The topmost region containing `x`, `y`.
+    pub fn at(&self, x: i32, y: i32) -> Option<Id> {
+        self.regions
+            .iter()
+            .rev()
+            .find(|(_, rect)| rect.contains(x, y))
+            .map(|&(id, _)| id)
+    }
+
+    /// Works out which regions the pointer is over and which ones its buttons went down on. Call
+    /// once per frame, after updating the mouse and adding this frame's regions.
+    pub fn update(&mut self, mouse: &Mouse) {
+        self.mouse = *mouse;
+        self.hovered = if mouse.on_screen() {
+            self.at(mouse.x, mouse.y)
+        } else {
+            None
+        };
+        for button in MouseButton::ALL {
+            if mouse.pressed(button) {
+                self.pressed_on[index(button)] = self.hovered;
+            }
+        }
+    }
+
+    /// The topmost region under the pointer.
+    pub fn hovered(&self) -> Option<Id> {
+        self.hovered
+    }
+
+    pub fn is_hovered(&self, id: Id) -> bool {
+        self.hovered == Some(id)
+    }
+
+    /// The region `button` went down on this frame.
+    pub fn pressed(&self, button: MouseButton) -> Option<Id> {
+        self.pressed_on[index(button)].filter(|_| self.mouse.pressed(button))
+    }
+
+    /// The region clicked with `button` this frame, which it has to have both gone down and come
+    /// up on.
+    pub fn clicked(&self, button: MouseButton) -> Option<Id> {
+        let pressed_on = self.pressed_on[index(button)];
+        pressed_on.filter(|_| self.mouse.clicked(button) && self.hovered == pressed_on)
+    }
+
+    /// The region double-clicked with `button` this frame, which the second click has to have
+    /// gone down and come up on.
+    pub fn double_clicked(&self, button: MouseButton) -> Option<Id> {
+        self.clicked(button)
+            .filter(|_| self.mouse.double_clicked(button))
+    }
+
+    /// The drag in progress and the region its button went down on, if any.
+    pub fn dragged(&self) -> Option<(Id, &Drag)> {
+        self.with_region(self.mouse.drag())
+    }
+
+    /// The drag that started this frame and the region its button went down on.
+    pub fn drag_started(&self) -> Option<(Id, &Drag)> {
+        self.with_region(self.mouse.drag_started())
+    }
+
+    /// The drag that ended this frame and the region its button went down on.
+    pub fn drag_ended(&self) -> Option<(Id, &Drag)> {
+        self.with_region(self.mouse.drag_ended())
+    }
+
+    /// The region `drag` was started on, along with the drag.
+    fn with_region<'b>(&self, drag: Option<&'b Drag>) -> Option<(Id, &'b Drag)> {
+        let drag = drag?;
+        Some((self.pressed_on[index(drag.button)]?, drag))
+    }
+
+    /// The region under the pointer when the drag ended, for dropping whatever was dragged onto.
+    pub fn drop_target(&self) -> Option<Id> {
+        self.mouse.drag_ended().and(self.hovered)
+    }
+}
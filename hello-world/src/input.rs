@@ -0,0 +1,468 @@
+use crate::{
+    geometry::Rect,
+    wasm4::{
+        BUTTON_1, BUTTON_2, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP, MOUSE_LEFT,
+        MOUSE_MIDDLE, MOUSE_RIGHT, SCREEN_SIZE,
+    },
+    wasm4_mmio::{GAMEPAD1, GAMEPAD2, GAMEPAD3, GAMEPAD4, MOUSE_BUTTONS, MOUSE_X, MOUSE_Y},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Button {
+    // BUTTON_1, the X key on a keyboard
+    X,
+    // BUTTON_2, the Z key on a keyboard
+    Z,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Button {
+    pub const ALL: [Button; 6] = [
+        Button::X,
+        Button::Z,
+        Button::Left,
+        Button::Right,
+        Button::Up,
+        Button::Down,
+    ];
+
+    /// The button's bit in a `GAMEPAD` register.
+    pub const fn bit(self) -> u8 {
+        match self {
+            Button::X => BUTTON_1,
+            Button::Z => BUTTON_2,
+            Button::Left => BUTTON_LEFT,
+            Button::Right => BUTTON_RIGHT,
+            Button::Up => BUTTON_UP,
+            Button::Down => BUTTON_DOWN,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+impl MouseButton {
+    pub const ALL: [MouseButton; 3] = [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+    /// The button's bit in the `MOUSE_BUTTONS` register.
+    pub const fn bit(self) -> u8 {
+        match self {
+            MouseButton::Left => MOUSE_LEFT,
+            MouseButton::Right => MOUSE_RIGHT,
+            MouseButton::Middle => MOUSE_MIDDLE,
+        }
+    }
+}
+
+/// This frame's and last frame's state of a set of buttons, stored as bits the way WASM-4 reports
+/// them, along with how long each has been held.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+struct Buttons {
+    held: u8,
+    previous: u8,
+    // consecutive frames each bit has been set for, indexed by bit position
+    held_frames: [u32; 8],
+}
+
+impl Buttons {
+    const fn new() -> Self {
+        Self {
+            held: 0,
+            previous: 0,
+            held_frames: [0; 8],
+        }
+    }
+
+    fn update(&mut self, bits: u8) {
+        self.previous = self.held;
+        self.held = bits;
+        for (i, frames) in self.held_frames.iter_mut().enumerate() {
+            *frames = if bits & (1 << i) != 0 {
+                frames.saturating_add(1)
+            } else {
+                0
+            };
+        }
+    }
+
+    fn held(&self, bit: u8) -> bool {
+        self.held & bit != 0
+    }
+
+    fn pressed(&self, bit: u8) -> bool {
+        self.held & bit != 0 && self.previous & bit == 0
+    }
+
+    fn released(&self, bit: u8) -> bool {
+        self.held & bit == 0 && self.previous & bit != 0
+    }
+
+    fn held_frames(&self, bit: u8) -> u32 {
+        self.held_frames[bit.trailing_zeros() as usize]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Gamepad {
+    buttons: Buttons,
+}
+
+impl Gamepad {
+    /// Whether `button` is down this frame.
+    pub fn held(&self, button: Button) -> bool {
+        self.buttons.held(button.bit())
+    }
+
+    /// Whether `button` went down this frame.
+    pub fn pressed(&self, button: Button) -> bool {
+        self.buttons.pressed(button.bit())
+    }
+
+    /// Whether `button` came up this frame.
+    pub fn released(&self, button: Button) -> bool {
+        self.buttons.released(button.bit())
+    }
+
+    /// How many frames in a row `button` has been down, counting this one, or 0 if it's up.
+    pub fn held_frames(&self, button: Button) -> u32 {
+        self.buttons.held_frames(button.bit())
+    }
+
+    /// This frame's `GAMEPAD` register value.
+    pub fn bits(&self) -> u8 {
+        self.buttons.held
+    }
+
+    /// Last frame's `GAMEPAD` register value.
+    pub fn previous_bits(&self) -> u8 {
+        self.buttons.previous
+    }
+}
+
+/// How far the pointer has to move with a button held down before it counts as a drag rather
+/// than a click.
+pub const DRAG_THRESHOLD: i32 = 3;
+/// How many frames apart two clicks can be and still count as a double-click.
+pub const DOUBLE_CLICK_FRAMES: u32 = 20;
+/// How far apart two clicks can be and still count as a double-click.
+pub const DOUBLE_CLICK_DISTANCE: i32 = 4;
+
+/// The pointer moving with a mouse button held down.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Drag {
+    pub button: MouseButton,
+    // where the button went down
+    pub start_x: i32,
+    pub start_y: i32,
+    pub x: i32,
+    pub y: i32,
+    pub previous_x: i32,
+    pub previous_y: i32,
+}
+
+impl Drag {
+    /// How far the pointer has moved since the drag started.
+    pub fn offset(&self) -> (i32, i32) {
+        (self.x - self.start_x, self.y - self.start_y)
+    }
+
+    /// How far the pointer moved this frame.
+    pub fn delta(&self) -> (i32, i32) {
+        (self.x - self.previous_x, self.y - self.previous_y)
+    }
+}
+
+/// The mouse pointer and buttons. The position is always kept on the screen: when the pointer
+/// leaves it, the position stays clamped to the nearest edge, nothing is hovered, and buttons
+/// pressed out there are ignored, though buttons already held stay held so drags carry on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Mouse {
+    pub x: i32,
+    pub y: i32,
+    pub previous_x: i32,
+    pub previous_y: i32,
+    on_screen: bool,
+    buttons: Buttons,
+    // where each button last went down, indexed by bit position
+    press_positions: [(i32, i32); 3],
+    // where and how many frames ago each button was last clicked, for spotting double-clicks
+    last_clicks: [(i32, i32, u32); 3],
+    clicked: u8,
+    double_clicked: u8,
+    drag: Option<Drag>,
+    drag_started: bool,
+    // the drag that ended this frame
+    drag_ended: Option<Drag>,
+}
+
+impl Default for Mouse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mouse {
+    const fn new() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            previous_x: 0,
+            previous_y: 0,
+            on_screen: false,
+            buttons: Buttons::new(),
+            press_positions: [(0, 0); 3],
+            last_clicks: [(0, 0, u32::MAX); 3],
+            clicked: 0,
+            double_clicked: 0,
+            drag: None,
+            drag_started: false,
+            drag_ended: None,
+        }
+    }
+
+    fn update(&mut self, x: i32, y: i32, bits: u8) {
+        let max = SCREEN_SIZE as i32 - 1;
+        self.on_screen = (0..=max).contains(&x) && (0..=max).contains(&y);
+        self.previous_x = self.x;
+        self.previous_y = self.y;
+        self.x = x.clamp(0, max);
+        self.y = y.clamp(0, max);
+        let bits = if self.on_screen {
+            bits
+        } else {
+            bits & self.buttons.held
+        };
+        self.buttons.update(bits);
+
+        self.clicked = 0;
+        self.double_clicked = 0;
+        self.drag_started = false;
+        self.drag_ended = None;
+        for (i, button) in MouseButton::ALL.into_iter().enumerate() {
+            if self.pressed(button) {
+                self.press_positions[i] = (self.x, self.y);
+            }
+            let dragged = self.drag.is_some_and(|drag| drag.button == button);
+            let released = self.released(button);
+            let last_click = &mut self.last_clicks[i];
+            last_click.2 = last_click.2.saturating_add(1);
+            if released && !dragged && self.on_screen {
+                self.clicked |= button.bit();
+                let (click_x, click_y, frames) = *last_click;
+                if frames <= DOUBLE_CLICK_FRAMES
+                    && (self.x - click_x).abs() <= DOUBLE_CLICK_DISTANCE
+                    && (self.y - click_y).abs() <= DOUBLE_CLICK_DISTANCE
+                {
+                    self.double_clicked |= button.bit();
+                    // a third click starts over rather than making another double-click
+                    *last_click = (self.x, self.y, u32::MAX);
+                } else {
+                    *last_click = (self.x, self.y, 0);
+                }
+            }
+        }
+
+        match &mut self.drag {
+            Some(drag) if self.buttons.held(drag.button.bit()) => {
+                drag.previous_x = drag.x;
+                drag.previous_y = drag.y;
+                drag.x = self.x;
+                drag.y = self.y;
+            }
+            Some(drag) => {
+                self.drag_ended = Some(*drag);
+                self.drag = None;
+            }
+            None => {
+                // only one drag at a time, with whichever button moved far enough first
+                let (x, y) = (self.x, self.y);
+                self.drag = MouseButton::ALL
+                    .into_iter()
+                    .enumerate()
+                    .find_map(|(i, button)| {
+                        let (start_x, start_y) = self.press_positions[i];
+                        let far_enough =
+                            (x - start_x).abs().max((y - start_y).abs()) >= DRAG_THRESHOLD;
+                        (self.buttons.held(button.bit()) && far_enough).then_some(Drag {
+                            button,
+                            start_x,
+                            start_y,
+                            x,
+                            y,
+                            previous_x: self.previous_x,
+                            previous_y: self.previous_y,
+                        })
+                    });
+                self.drag_started = self.drag.is_some();
+            }
+        }
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    pub fn moved(&self) -> bool {
+        self.x != self.previous_x || self.y != self.previous_y
+    }
+
+    /// Whether the pointer is over the screen. When it isn't, `x` and `y` are clamped to the edge.
+    pub fn on_screen(&self) -> bool {
+        self.on_screen
+    }
+
+    /// Whether the pointer is over `rect`, which is never the case while it's off the screen.
+    pub fn hovering(&self, rect: &Rect) -> bool {
+        self.on_screen && rect.contains(self.x, self.y)
+    }
+
+    pub fn held(&self, button: MouseButton) -> bool {
+        self.buttons.held(button.bit())
+    }
+
+    pub fn pressed(&self, button: MouseButton) -> bool {
+        self.buttons.pressed(button.bit())
+    }
+
+    pub fn released(&self, button: MouseButton) -> bool {
+        self.buttons.released(button.bit())
+    }
+
+    pub fn held_frames(&self, button: MouseButton) -> u32 {
+        self.buttons.held_frames(button.bit())
+    }
+
+    /// Whether `button` was released over the screen this frame without the pointer being dragged.
+    pub fn clicked(&self, button: MouseButton) -> bool {
+        self.clicked & button.bit() != 0
+    }
+
+    /// Whether this frame's click of `button` was the second of two in the same place within
+    /// `DOUBLE_CLICK_FRAMES`. The first click is reported by `clicked` as well.
+    pub fn double_clicked(&self, button: MouseButton) -> bool {
+        self.double_clicked & button.bit() != 0
+    }
+
+    /// The drag in progress, including on the frame it starts but not the frame it ends.
+    pub fn drag(&self) -> Option<&Drag> {
+        self.drag.as_ref()
+    }
+
+    /// The drag that started this frame, once the pointer moved `DRAG_THRESHOLD` pixels from where
+    /// its button went down.
+    pub fn drag_started(&self) -> Option<&Drag> {
+        self.drag.as_ref().filter(|_| self.drag_started)
+    }
+
+    /// The drag that ended this frame, with its button released.
+    pub fn drag_ended(&self) -> Option<&Drag> {
+        self.drag_ended.as_ref()
+    }
+
+    /// How far to scroll the contents of `area` this frame, for scrolling by dragging them with
+    /// the left button: the opposite of how far the pointer moved, if the drag started inside
+    /// `area`, so the contents follow the pointer.
+    pub fn drag_scroll(&self, area: &Rect) -> (i32, i32) {
+        match self.drag {
+            Some(drag)
+                if drag.button == MouseButton::Left
+                    && area.contains(drag.start_x, drag.start_y) =>
+            {
+                let (dx, dy) = drag.delta();
+                (-dx, -dy)
+            }
+            _ => (0, 0),
+        }
+    }
+
+    /// This frame's `MOUSE_BUTTONS` register value, leaving out buttons pressed off the screen.
+    pub fn bits(&self) -> u8 {
+        self.buttons.held
+    }
+
+    /// Last frame's `MOUSE_BUTTONS` register value, leaving out buttons pressed off the screen.
+    pub fn previous_bits(&self) -> u8 {
+        self.buttons.previous
+    }
+}
+
+/// The gamepad and mouse registers' values for one frame, as WASM-4 reported them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct InputFrame {
+    pub gamepads: [u8; 4],
+    pub mouse_x: i16,
+    pub mouse_y: i16,
+    pub mouse_buttons: u8,
+}
+
+impl InputFrame {
+    pub fn read() -> Self {
+        Self {
+            gamepads: [
+                GAMEPAD1.read(),
+                GAMEPAD2.read(),
+                GAMEPAD3.read(),
+                GAMEPAD4.read(),
+            ],
+            mouse_x: MOUSE_X.read(),
+            mouse_y: MOUSE_Y.read(),
+            mouse_buttons: MOUSE_BUTTONS.read(),
+        }
+    }
+}
+
+/// The state of all four gamepads and the mouse, this frame and last, so button presses and
+/// releases can be told apart from buttons that are simply held down. Call `update` once at the
+/// start of every frame.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Input {
+    gamepads: [Gamepad; 4],
+    mouse: Mouse,
+}
+
+impl Input {
+    pub const fn new() -> Self {
+        const GAMEPAD: Gamepad = Gamepad {
+            buttons: Buttons::new(),
+        };
+        Self {
+            gamepads: [GAMEPAD; 4],
+            mouse: Mouse::new(),
+        }
+    }
+
+    /// Reads this frame's state from the gamepad and mouse registers.
+    pub fn update(&mut self) {
+        self.update_with(&InputFrame::read());
+    }
+
+    /// Takes this frame's state from `frame` instead of reading the registers, such as to replay
+    /// a recording.
+    pub fn update_with(&mut self, frame: &InputFrame) {
+        for (gamepad, bits) in self.gamepads.iter_mut().zip(frame.gamepads) {
+            gamepad.buttons.update(bits);
+        }
+        self.mouse.update(
+            frame.mouse_x as i32,
+            frame.mouse_y as i32,
+            frame.mouse_buttons,
+        );
+    }
+
+    /// The gamepad of player `player`, from 0 to 3.
+    pub fn gamepad(&self, player: usize) -> &Gamepad {
+        &self.gamepads[player]
+    }
+
+    pub fn mouse(&self) -> &Mouse {
+        &self.mouse
+    }
+}
@@ -0,0 +1,348 @@
+use crate::input::{Button, Gamepad};
+
+/// How many events `InputHistory` keeps before the oldest are dropped.
+pub const HISTORY_LEN: usize = 32;
+
+const DPAD: u8 = Button::Left.bit() | Button::Right.bit() | Button::Up.bit() | Button::Down.bit();
+
+/// A change in which buttons are held on a gamepad.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct InputEvent {
+    // the frame it happened on, counted by the history it's in
+    pub frame: u32,
+    // as in the GAMEPAD registers, after and before the change
+    pub held: u8,
+    pub previous: u8,
+}
+
+impl InputEvent {
+    /// The buttons that went down in this event.
+    pub fn pressed(&self) -> u8 {
+        self.held & !self.previous
+    }
+}
+
+/// One step of a `Sequence`: a direction on the D-pad, and buttons held along with it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Step {
+    // the exact D-pad state, or 0 for any direction
+    dpad: u8,
+    buttons: u8,
+}
+
+impl Step {
+    pub const UP: Step = Step::direction(&[Button::Up]);
+    pub const UP_RIGHT: Step = Step::direction(&[Button::Up, Button::Right]);
+    pub const RIGHT: Step = Step::direction(&[Button::Right]);
+    pub const DOWN_RIGHT: Step = Step::direction(&[Button::Down, Button::Right]);
+    pub const DOWN: Step = Step::direction(&[Button::Down]);
+    pub const DOWN_LEFT: Step = Step::direction(&[Button::Down, Button::Left]);
+    pub const LEFT: Step = Step::direction(&[Button::Left]);
+    pub const UP_LEFT: Step = Step::direction(&[Button::Up, Button::Left]);
+
+    /// A step of holding exactly `buttons` on the D-pad, so a diagonal doesn't count as either of
+    /// its directions.
+    pub const fn direction(buttons: &[Button]) -> Self {
+        let mut dpad = 0;
+        let mut i = 0;
+        while i < buttons.len() {
+            dpad |= buttons[i].bit() & DPAD;
+            i += 1;
+        }
+        Self { dpad, buttons: 0 }
+    }
+
+    /// A step of pressing `button` with the D-pad in any direction.
+    pub const fn button(button: Button) -> Self {
+        Self {
+            dpad: 0,
+            buttons: button.bit(),
+        }
+    }
+
+    /// This step with `button` held along with it, such as → + X.
+    pub const fn with(self, button: Button) -> Self {
+        Self {
+            dpad: self.dpad,
+            buttons: self.buttons | button.bit(),
+        }
+    }
+
+    /// This step with left and right swapped, for moves input relative to the way a character
+    /// faces.
+    pub const fn mirrored(self) -> Self {
+        let (left, right) = (Button::Left.bit(), Button::Right.bit());
+        let mut dpad = self.dpad & !(left | right);
+        if self.dpad & left != 0 {
+            dpad |= right;
+        }
+        if self.dpad & right != 0 {
+            dpad |= left;
+        }
+        Self {
+            dpad,
+            buttons: self.buttons,
+        }
+    }
+
+    fn held_in(&self, bits: u8) -> bool {
+        (self.dpad == 0 || bits & DPAD == self.dpad) && bits & self.buttons == self.buttons
+    }
+
+    /// Whether `event` is the step being entered, rather than something else changing while it's
+    /// held.
+    fn entered_by(&self, event: &InputEvent) -> bool {
+        self.held_in(event.held) && !self.held_in(event.previous)
+    }
+}
+
+/// Steps that have to be entered in order, all within `within` frames, such as a fireball motion
+/// of ↓ ↘ → + X within 15 frames. Other presses in between are allowed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Sequence {
+    pub steps: &'static [Step],
+    pub within: u32,
+}
+
+/// A ring buffer of the changes to a gamepad's buttons over the last `HISTORY_LEN` changes,
+/// timestamped by frame, for moves made of several inputs in a row and for lenient timing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct InputHistory {
+    events: [InputEvent; HISTORY_LEN],
+    // where the oldest event is in `events`, and how many there are
+    start: usize,
+    len: usize,
+    frame: u32,
+}
+
+impl InputHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds this frame's changes to `gamepad`, if there were any. Call once per frame, after
+    /// updating the input.
+    pub fn update(&mut self, gamepad: &Gamepad) {
+        self.frame += 1;
+        if gamepad.bits() == gamepad.previous_bits() {
+            return;
+        }
+        let event = InputEvent {
+            frame: self.frame,
+            held: gamepad.bits(),
+            previous: gamepad.previous_bits(),
+        };
+        if self.len < HISTORY_LEN {
+            self.events[(self.start + self.len) % HISTORY_LEN] = event;
+            self.len += 1;
+        } else {
+            self.events[self.start] = event;
+            self.start = (self.start + 1) % HISTORY_LEN;
+        }
+    }
+
+    /// The number of frames since the history was created, which events are timestamped with.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+
+    /// The events in the history, most recent first.
+    pub fn events(&self) -> impl Iterator<Item = &InputEvent> {
+        (0..self.len)
+            .rev()
+            .map(|i| &self.events[(self.start + i) % HISTORY_LEN])
+    }
+
+    /// How many frames ago `button` last went down, if it's still in the history.
+    pub fn frames_since_pressed(&self, button: Button) -> Option<u32> {
+        self.events()
+            .find(|event| event.pressed() & button.bit() != 0)
+            .map(|event| self.frame - event.frame)
+    }
+
+    /// Whether `button` went down within the last `frames` frames, counting this one as 0.
+    pub fn pressed_within(&self, button: Button, frames: u32) -> bool {
+        self.frames_since_pressed(button)
+            .is_some_and(|since| since <= frames)
+    }
+
+    /// Whether `sequence` was finished this frame: its last step was entered this frame, and every
+    /// other step before it in order, with the first no more than `sequence.within` frames ago.
+    pub fn matched(&self, sequence: &Sequence) -> bool {
+        self.matches(sequence.steps.iter().copied(), sequence.within)
+    }
+
+    /// Like `matched`, but with left and right swapped in every step, for when a character faces
+    /// the other way.
+    pub fn matched_mirrored(&self, sequence: &Sequence) -> bool {
+        let steps = sequence.steps.iter().map(|step| step.mirrored());
+        self.matches(steps, sequence.within)
+    }
+
+    fn matches(&self, steps: impl DoubleEndedIterator<Item = Step>, within: u32) -> bool {
+        let mut steps = steps.rev();
+        let Some(last) = steps.next() else {
+            return false;
+        };
+        let mut events = self
+            .events()
+            .take_while(|event| self.frame - event.frame <= within);
+        // the last step has to be what happened this frame, so the move only comes out once
+        if !events
+            .next()
+            .is_some_and(|event| event.frame == self.frame && last.entered_by(event))
+        {
+            return false;
+        }
+        // the rest are matched working backwards, each to the latest event that enters it
+        steps.all(|step| events.any(|event| step.entered_by(event)))
+    }
+}
+
+/// Keeps something true for a few frames after it stops being, for lenient timing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GraceWindow {
+    pub window: u32,
+    frames_left: u32,
+}
+
+impl GraceWindow {
+    /// Stays active for `window` frames after the one it was last set on.
+    pub const fn new(window: u32) -> Self {
+        Self {
+            window,
+            frames_left: 0,
+        }
+    }
+
+    /// Counts down the time left, or starts over if `set` this frame. Call once per frame.
+    pub fn update(&mut self, set: bool) {
+        self.frames_left = if set {
+            self.window.saturating_add(1)
+        } else {
+            self.frames_left.saturating_sub(1)
+        };
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.frames_left > 0
+    }
+
+    /// Uses up the time left, returning whether it was active.
+    pub fn consume(&mut self) -> bool {
+        let active = self.is_active();
+        self.frames_left = 0;
+        active
+    }
+}
+
+/// Remembers a press for a few frames, so it still counts if it comes slightly too early, such as
+/// jump pressed just before landing. Update it with whether the button was pressed, and consume
+/// it when the press is used.
+pub type BufferedPress = GraceWindow;
+
+/// Keeps something allowed for a few frames after it stops being, such as jumping just after
+/// running off a ledge. Update it with whether it's allowed, such as while standing on the
+/// ground, and consume it once the jump has been made so it can't be made again in midair.
+pub type CoyoteTime = GraceWindow;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Input, InputFrame};
+
+    const FIREBALL: Sequence = Sequence {
+        steps: &[Step::DOWN, Step::DOWN_RIGHT, Step::RIGHT.with(Button::X)],
+        within: 15,
+    };
+
+    /// Feeds each frame's gamepad 1 bits through `Input` into a new history.
+    fn history(frames: &[u8]) -> InputHistory {
+        let mut input = Input::new();
+        let mut history = InputHistory::new();
+        for &bits in frames {
+            input.update_with(&InputFrame {
+                gamepads: [bits, 0, 0, 0],
+                mouse_x: 0,
+                mouse_y: 0,
+                mouse_buttons: 0,
+            });
+            history.update(input.gamepad(0));
+        }
+        history
+    }
+
+    const DOWN: u8 = Button::Down.bit();
+    const DOWN_RIGHT: u8 = Button::Down.bit() | Button::Right.bit();
+    const DOWN_LEFT: u8 = Button::Down.bit() | Button::Left.bit();
+    const RIGHT_X: u8 = Button::Right.bit() | Button::X.bit();
+    const LEFT_X: u8 = Button::Left.bit() | Button::X.bit();
+
+    #[test]
+    fn sequences_match_on_the_frame_their_last_step_is_entered() {
+        assert!(history(&[DOWN, DOWN_RIGHT, RIGHT_X]).matched(&FIREBALL));
+        // and only then, so the move doesn't come out again while X is held
+        assert!(!history(&[DOWN, DOWN_RIGHT, RIGHT_X, RIGHT_X]).matched(&FIREBALL));
+        assert!(!history(&[DOWN, DOWN_RIGHT, RIGHT_X, 0]).matched(&FIREBALL));
+    }
+
+    #[test]
+    fn steps_have_to_be_entered_in_order() {
+        assert!(!history(&[DOWN_RIGHT, DOWN, RIGHT_X]).matched(&FIREBALL));
+        // a diagonal doesn't count as either of its directions
+        assert!(!history(&[DOWN, Button::Right.bit(), RIGHT_X]).matched(&FIREBALL));
+    }
+
+    #[test]
+    fn sequences_have_to_be_finished_within_their_window() {
+        // down was entered 15 frames before the end
+        let mut frames = vec![DOWN; 14];
+        frames.extend([DOWN_RIGHT, RIGHT_X]);
+        assert!(history(&frames).matched(&FIREBALL));
+
+        let mut frames = vec![DOWN; 15];
+        frames.extend([DOWN_RIGHT, RIGHT_X]);
+        assert!(!history(&frames).matched(&FIREBALL));
+    }
+
+    #[test]
+    fn other_presses_in_between_are_allowed() {
+        let z = Button::Z.bit();
+        let frames = [DOWN, DOWN | z, DOWN_RIGHT, DOWN_RIGHT | z, RIGHT_X];
+        assert!(history(&frames).matched(&FIREBALL));
+    }
+
+    #[test]
+    fn mirrored_sequences_swap_left_and_right() {
+        let history = history(&[DOWN, DOWN_LEFT, LEFT_X]);
+        assert!(history.matched_mirrored(&FIREBALL));
+        assert!(!history.matched(&FIREBALL));
+        assert!(!self::history(&[DOWN, DOWN_RIGHT, RIGHT_X]).matched_mirrored(&FIREBALL));
+    }
+
+    #[test]
+    fn grace_windows_last_window_frames_after_being_set() {
+        let mut grace = GraceWindow::new(2);
+        grace.update(true);
+        for _ in 0..2 {
+            grace.update(false);
+            assert!(grace.is_active());
+        }
+        grace.update(false);
+        assert!(!grace.is_active());
+    }
+
+    #[test]
+    fn consuming_a_grace_window_ends_it() {
+        let mut grace = GraceWindow::new(u32::MAX);
+        grace.update(true);
+        assert!(grace.consume());
+        assert!(!grace.is_active());
+        assert!(!grace.consume());
+    }
+}
@@ -0,0 +1,303 @@
+use core::fmt;
+
+use crate::{
+    font::SpriteFont,
+    geometry::Rect,
+    input::{Button, Input, MouseButton},
+    sprite::Texture,
+    ui::UiStyle,
+    wasm4,
+    wasm4_mmio::DRAW_COLORS,
+};
+
+// space between a key's frame and its glyph
+const PADDING: u32 = 1;
+const BACKSPACE_LABEL: &str = "DEL";
+const CONFIRM_LABEL: &str = "OK";
+
+/// A string stored inline in up to `N` bytes of UTF-8, so it needs no allocation.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct FixedString<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> FixedString<N> {
+    pub const fn new() -> Self {
+        Self {
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // only whole characters are ever pushed, so this is always valid UTF-8
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap()
+    }
+
+    /// Appends `c`, returning false if there isn't room for it.
+    pub fn push(&mut self, c: char) -> bool {
+        if self.len + c.len_utf8() > N {
+            return false;
+        }
+        c.encode_utf8(&mut self.bytes[self.len..]);
+        self.len += c.len_utf8();
+        true
+    }
+
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.as_str().chars().next_back()?;
+        self.len -= c.len_utf8();
+        Some(c)
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of characters in the string.
+    pub fn char_count(&self) -> usize {
+        self.as_str().chars().count()
+    }
+}
+
+impl<const N: usize> Default for FixedString<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Display for FixedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> fmt::Debug for FixedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Key {
+    Char(char),
+    Backspace,
+    Confirm,
+}
+
+/// An on-screen keyboard for typing text with the gamepad or mouse, laid out as a grid of every
+/// printable character in a font, followed by backspace and confirm keys. The D-pad moves the
+/// cursor, button 1 presses the key under it and button 2 is a shortcut for backspace; with the
+/// mouse, clicking a key presses it.
+pub struct Keyboard<'a, T: Texture, const N: usize> {
+    pub font: &'a SpriteFont<'a, T>,
+    pub style: UiStyle,
+    keys: Vec<Key>,
+    columns: usize,
+    cursor: usize,
+    // in characters, which may be fewer than will fit in N bytes
+    max_len: usize,
+    text: FixedString<N>,
+}
+
+impl<'a, T: Texture, const N: usize> Keyboard<'a, T, N> {
+    /// Lays out the font's characters `columns` keys across. Spaces are kept, but control
+    /// characters and named glyphs in the Private Use Area, such as button icons, are left out.
+    pub fn new(
+        font: &'a SpriteFont<'a, T>,
+        style: UiStyle,
+        columns: usize,
+        max_len: usize,
+    ) -> Self {
+        let mut keys: Vec<Key> = font
+            .char_map
+            .iter()
+            .flat_map(|range| {
+                (range.first as u32..range.first as u32 + range.len).filter_map(char::from_u32)
+            })
+            .filter(|c| !c.is_control() && !('\u{e000}'..='\u{f8ff}').contains(c))
+            .map(Key::Char)
+            .collect();
+        keys.push(Key::Backspace);
+        keys.push(Key::Confirm);
+        Self {
+            font,
+            style,
+            keys,
+            columns: columns.max(1),
+            cursor: 0,
+            max_len,
+            text: FixedString::new(),
+        }
+    }
+
+    pub fn text(&self) -> &FixedString<N> {
+        &self.text
+    }
+
+    /// Starts over with `text` already typed in and the cursor on the first key.
+    pub fn reset(&mut self, text: &str) {
+        self.text.clear();
+        for c in text.chars().take(self.max_len) {
+            self.text.push(c);
+        }
+        self.cursor = 0;
+    }
+
+    fn key_size(&self) -> (u32, u32) {
+        let glyph_width = self.font.glyphs.iter().map(|glyph| glyph.width).max();
+        (
+            glyph_width.unwrap_or(0) + PADDING * 2,
+            self.font.glyph_height + PADDING * 2,
+        )
+    }
+
+    /// The area of the key at `index` when the keyboard's top-left corner is at `x`, `y`. The
+    /// backspace and confirm keys share the last row, each half of it wide.
+    fn key_rect(&self, index: usize, x: i32, y: i32) -> Rect {
+        let (key_width, key_height) = self.key_size();
+        let grid_y = y + (key_height + PADDING) as i32;
+        let char_keys = self.keys.len() - 2;
+        if index < char_keys {
+            let (column, row) = (index % self.columns, index / self.columns);
+            return Rect::new(
+                x + (column as u32 * key_width) as i32,
+                grid_y + (row as u32 * key_height) as i32,
+                key_width,
+                key_height,
+            );
+        }
+        let rows = char_keys.div_ceil(self.columns) as u32;
+        let half_width = self.columns as u32 * key_width / 2;
+        Rect::new(
+            x + ((index - char_keys) as u32 * half_width) as i32,
+            grid_y + (rows * key_height) as i32,
+            half_width,
+            key_height,
+        )
+    }
+
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let char_keys = self.keys.len() - 2;
+        let rows = char_keys.div_ceil(self.columns) as i32;
+        if self.cursor >= char_keys {
+            // the special keys act as a row of two, under the left and right halves of the grid
+            let special = (self.cursor - char_keys) as i32;
+            if dx != 0 {
+                self.cursor = char_keys + (special + dx).rem_euclid(2) as usize;
+            } else if dy != 0 && char_keys > 0 {
+                let column = special as usize * self.columns / 2;
+                let row = if dy > 0 { 0 } else { rows - 1 };
+                self.cursor = (row as usize * self.columns + column).min(char_keys - 1);
+            }
+            return;
+        }
+        let (column, row) = (
+            (self.cursor % self.columns) as i32,
+            (self.cursor / self.columns) as i32,
+        );
+        if dx != 0 {
+            let row_len = (char_keys - row as usize * self.columns).min(self.columns) as i32;
+            self.cursor = row as usize * self.columns + (column + dx).rem_euclid(row_len) as usize;
+        } else if dy != 0 {
+            let row = row + dy;
+            self.cursor = if row < 0 || row >= rows {
+                char_keys + (column as usize * 2 / self.columns).min(1)
+            } else {
+                (row as usize * self.columns + column as usize).min(char_keys - 1)
+            };
+        }
+    }
+
+    fn press(&mut self, key: Key) -> bool {
+        match key {
+            Key::Char(c) => {
+                if self.text.char_count() < self.max_len {
+                    self.text.push(c);
+                }
+            }
+            Key::Backspace => {
+                self.text.pop();
+            }
+            Key::Confirm => return true,
+        }
+        false
+    }
+
+    /// Handles this frame's input from the first gamepad and the mouse, and draws the keyboard
+    /// with its top-left corner at `x`, `y`, with the text typed so far above the keys. Returns
+    /// the text once it's confirmed. Call once per frame while the keyboard is shown.
+    pub fn update(&mut self, input: &Input, x: i32, y: i32) -> Option<FixedString<N>> {
+        let gamepad = input.gamepad(0);
+        for (button, dx, dy) in [
+            (Button::Left, -1, 0),
+            (Button::Right, 1, 0),
+            (Button::Up, 0, -1),
+            (Button::Down, 0, 1),
+        ] {
+            if gamepad.pressed(button) {
+                self.move_cursor(dx, dy);
+            }
+        }
+        let mut confirmed = false;
+        if gamepad.pressed(Button::X) {
+            confirmed |= self.press(self.keys[self.cursor]);
+        }
+        if gamepad.pressed(Button::Z) {
+            self.press(Key::Backspace);
+        }
+        let mouse = input.mouse();
+        let clicked = mouse.pressed(MouseButton::Left);
+        if mouse.moved() || clicked {
+            let hovered = (0..self.keys.len()).find(|&i| mouse.hovering(&self.key_rect(i, x, y)));
+            if let Some(hovered) = hovered {
+                self.cursor = hovered;
+                if clicked {
+                    confirmed |= self.press(self.keys[hovered]);
+                }
+            }
+        }
+
+        self.draw(x, y);
+        confirmed.then_some(self.text)
+    }
+
+    fn draw(&self, x: i32, y: i32) {
+        // the text so far, followed by an underscore for each character left
+        let mut field = String::from(self.text.as_str());
+        for _ in self.text.char_count()..self.max_len {
+            field.push('_');
+        }
+        DRAW_COLORS.write(self.style.text_colors);
+        self.font
+            .blit_string(&field, x + PADDING as i32, y + PADDING as i32);
+
+        for (i, key) in self.keys.iter().enumerate() {
+            let rect = self.key_rect(i, x, y);
+            let focused = i == self.cursor;
+            if focused {
+                DRAW_COLORS.write(self.style.focused_frame_colors);
+                wasm4::rect(rect.x, rect.y, rect.width, rect.height);
+            }
+            let mut buf = [0; 4];
+            let label = match key {
+                Key::Char(c) => &*c.encode_utf8(&mut buf),
+                Key::Backspace => BACKSPACE_LABEL,
+                Key::Confirm => CONFIRM_LABEL,
+            };
+            let label_x = rect.x + (rect.width as i32 - self.font.measure_line(label) as i32) / 2;
+            DRAW_COLORS.write(if focused {
+                self.style.focused_text_colors
+            } else {
+                self.style.text_colors
+            });
+            self.font
+                .blit_string(label, label_x, rect.y + PADDING as i32);
+        }
+    }
+}
@@ -1,65 +1,114 @@
+// the modules are a toolkit for carts, and this demo only uses part of it
+#![allow(dead_code)]
+// the statics holding the cart's state are named like the locals they're read into
+#![allow(non_upper_case_globals)]
+
+// host-side tests use the system allocator, since the test harness needs more than the cart heap
+#[cfg(not(test))]
 mod alloc;
+
+mod actions;
 mod assets;
+mod camera;
+mod collision;
+mod compress;
+mod draw_list;
+mod font;
+mod framebuffer;
+mod geometry;
+mod hit_regions;
+mod input;
+mod input_history;
+mod keyboard;
+mod markup;
+mod nine_slice;
+mod palette;
+mod pattern;
+mod render_target;
+mod replay;
+mod rle;
+mod rng;
+mod session;
 mod sfx;
+mod shapes;
 mod sprite;
+mod text;
+mod tilemap;
+mod transition;
+mod ui;
 mod wasm4;
 mod wasm4_mmio;
-use assets::{BALL_SPRITE, BOLD_7X5_FONT};
-use sfx::{MusicNote, SoundPlayer};
+use assets::{BALL_SPRITE, BOLD_7X5_FONT, BOLD_7X5_FONT_BUTTON_X, BOLD_7X5_OUTLINE_FONT, PALETTES};
+use draw_list::DrawList;
+use framebuffer::{Framebuffer, Surface};
+use input::Button;
+use palette::PaletteManager;
+use session::Session;
+use sfx::SoundPlayer;
 use sync_unsafe_cell::SyncUnsafeCell;
-use wasm4::{blit, text, BLIT_1BPP, BUTTON_1, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP};
-use wasm4_mmio::{DRAW_COLORS, FRAMEBUFFER, GAMEPAD1, PALETTE};
-
-// Palettes from https://itch.io/jam/gbpixelartjam24
-const PALETTE_BLOOD_TIDE: [u32; 4] = [0x652121, 0x394a5a, 0x7a968d, 0xfffeea];
-const PALETTE_FORGOTTEN_SWAMP: [u32; 4] = [0x3b252e, 0x593a5f, 0x4d7d65, 0xd1ada1];
-const PALETTE_HOMEWORK: [u32; 4] = [0x12121b, 0x45568d, 0x878c9d, 0xe1d8d4];
-const PALETTE_MANGAVANIA: [u32; 4] = [0x6e1a4b, 0xe64ca4, 0x4aedff, 0xffffff];
+use wasm4::SCREEN_SIZE;
+use wasm4_mmio::DRAW_COLORS;
 
 static sound_player_cell: SyncUnsafeCell<Option<SoundPlayer>> = SyncUnsafeCell::new(None);
-static did_play_cell: SyncUnsafeCell<bool> = SyncUnsafeCell::new(false);
+static draw_list_cell: SyncUnsafeCell<Option<DrawList<'static>>> = SyncUnsafeCell::new(None);
+static palette_manager_cell: SyncUnsafeCell<Option<PaletteManager>> = SyncUnsafeCell::new(None);
+static session_cell: SyncUnsafeCell<Session> = SyncUnsafeCell::new(Session::new(0x2545_f491));
 
 #[no_mangle]
 fn start() {
-    PALETTE.write(PALETTE_BLOOD_TIDE);
+    let mut palette_manager = PaletteManager::new(&PALETTES);
+    palette_manager.fade_from_black("blood tide", 30);
+    unsafe { *(palette_manager_cell.get()) = Some(palette_manager) }
     unsafe { *(sound_player_cell.get()) = Some(SoundPlayer::new()) }
+    unsafe { *(draw_list_cell.get()) = Some(DrawList::new()) }
 }
 
 #[no_mangle]
 fn update() {
-    // clear buffer to color 3
-    FRAMEBUFFER.iter().for_each(|addr| addr.write(0xff));
+    let session = unsafe { session_cell.get().as_mut().unwrap() };
+    session.update();
+
+    Framebuffer::screen().clear(3);
     DRAW_COLORS.write(0x0002);
-    BOLD_7X5_FONT.draw_string("Hello from Rust!", 10, 10);
 
     let sound_player = unsafe { sound_player_cell.get().as_mut().unwrap() }
         .as_mut()
         .unwrap();
+    let draw_list = unsafe { draw_list_cell.get().as_mut().unwrap() }
+        .as_mut()
+        .unwrap();
+    let palette_manager = unsafe { palette_manager_cell.get().as_mut().unwrap() }
+        .as_mut()
+        .unwrap();
 
-    let gamepad = GAMEPAD1.read();
-    if gamepad & BUTTON_UP != 0 {
-        PALETTE.write(PALETTE_BLOOD_TIDE);
+    draw_list.text(1, &BOLD_7X5_OUTLINE_FONT, "Hello from Rust!", 10, 10);
+
+    let gamepad = session.gamepad(0);
+    if gamepad.pressed(Button::Up) {
+        palette_manager.fade_to("blood tide", 15);
     }
-    if gamepad & BUTTON_LEFT != 0 {
-        PALETTE.write(PALETTE_FORGOTTEN_SWAMP);
+    if gamepad.pressed(Button::Left) {
+        palette_manager.fade_to("forgotten swamp", 15);
     }
-    if gamepad & BUTTON_DOWN != 0 {
-        PALETTE.write(PALETTE_HOMEWORK);
+    if gamepad.pressed(Button::Down) {
+        palette_manager.fade_to("homework", 15);
     }
-    if gamepad & BUTTON_RIGHT != 0 {
-        PALETTE.write(PALETTE_MANGAVANIA);
+    if gamepad.pressed(Button::Right) {
+        palette_manager.fade_to("mangavania", 15);
     }
-    if gamepad & BUTTON_1 != 0 {
-        let did_play = unsafe { did_play_cell.get().as_mut().unwrap() };
-        if !*(did_play) {
-            *did_play = true;
-            sound_player.play(&assets::TOTAKAS_SONG);
-        }
+    if gamepad.pressed(Button::X) {
+        sound_player.play(&assets::TOTAKAS_SONG);
+        palette_manager.flash(palette::WHITE.colors, 3);
     }
 
     sound_player.update();
+    palette_manager.update();
+
+    draw_list.sprite(0, &BALL_SPRITE, 76, 76, 0);
 
-    BALL_SPRITE.draw(76, 76, 0);
+    let prompt = format!("Press {} to play note", BOLD_7X5_FONT_BUTTON_X);
+    let prompt_x = (SCREEN_SIZE as i32 - BOLD_7X5_FONT.measure_line(&prompt) as i32) / 2;
+    draw_list.text(1, &BOLD_7X5_FONT, prompt, prompt_x, 100);
 
-    BOLD_7X5_FONT.draw_string("Press X to play note", 16, 100);
+    draw_list.flush();
 }
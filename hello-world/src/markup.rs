@@ -0,0 +1,233 @@
+//! A small markup language for `SpriteFont` text:
+//!
+//! - `{c2}` draws the following text in palette color 2 (any of 0 to 4, where 0 is transparent)
+//! - `{dc:4320}` sets all of `DRAW_COLORS` for the following text, in hex
+//! - `{/c}` goes back to the color in use before the last `{c}` or `{dc}`
+//! - `{icon:name}` draws the icon called `name` from the `MarkupStyle`
+//! - `{wave}`/`{/wave}` and `{shake}`/`{/shake}` animate the text between them
+//! - `{{` is a literal `{`
+//!
+//! Anything else in braces is drawn as-is.
+
+use crate::{
+    font::SpriteFont,
+    sprite::{Sprite, Texture},
+    text::{TextItem, TextItemKind},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Effect {
+    Wave,
+    Shake,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tag<'t> {
+    Color(u8),
+    DrawColors(u16),
+    EndColor,
+    Icon(&'t str),
+    Effect(Effect),
+    EndEffect(Effect),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Token<'t> {
+    Char(char),
+    Tag(Tag<'t>),
+}
+
+fn parse_tag(tag: &str) -> Option<Tag<'_>> {
+    let tag = match tag {
+        "/c" => Tag::EndColor,
+        "wave" => Tag::Effect(Effect::Wave),
+        "/wave" => Tag::EndEffect(Effect::Wave),
+        "shake" => Tag::Effect(Effect::Shake),
+        "/shake" => Tag::EndEffect(Effect::Shake),
+        _ => {
+            if let Some(name) = tag.strip_prefix("icon:") {
+                Tag::Icon(name)
+            } else if let Some(hex) = tag.strip_prefix("dc:") {
+                Tag::DrawColors(u16::from_str_radix(hex, 16).ok()?)
+            } else {
+                let color = tag.strip_prefix('c')?.parse().ok()?;
+                if color > 4 {
+                    return None;
+                }
+                Tag::Color(color)
+            }
+        }
+    };
+    Some(tag)
+}
+
+/// Splits markup into characters and tags, along with the byte range each came from.
+pub struct Tokens<'t> {
+    s: &'t str,
+    pos: usize,
+}
+
+impl<'t> Tokens<'t> {
+    pub fn new(s: &'t str) -> Self {
+        Self { s, pos: 0 }
+    }
+}
+
+impl<'t> Iterator for Tokens<'t> {
+    type Item = (usize, usize, Token<'t>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.pos;
+        let rest = &self.s[start..];
+        let c = rest.chars().next()?;
+        if c == '{' {
+            if rest.starts_with("{{") {
+                self.pos += 2;
+                return Some((start, self.pos, Token::Char('{')));
+            }
+            if let Some(end) = rest.find('}') {
+                if let Some(tag) = parse_tag(&rest[1..end]) {
+                    self.pos += end + 1;
+                    return Some((start, self.pos, Token::Tag(tag)));
+                }
+            }
+        }
+        self.pos += c.len_utf8();
+        Some((start, self.pos, Token::Char(c)))
+    }
+}
+
+/// An icon that can be drawn inline with `{icon:name}`.
+pub struct MarkupIcon<'a, T: Texture> {
+    pub name: &'a str,
+    pub sprite: Sprite<'a, T>,
+}
+
+pub struct MarkupStyle<'a, T: Texture> {
+    pub icons: &'a [MarkupIcon<'a, T>],
+    // drives the `{wave}` and `{shake}` effects; pass a counter that goes up once per frame
+    pub frame: u32,
+}
+
+impl<'a, T: Texture> MarkupStyle<'a, T> {
+    pub fn icon(&self, name: &str) -> Option<&Sprite<'a, T>> {
+        self.icons
+            .iter()
+            .find(|icon| icon.name == name)
+            .map(|icon| &icon.sprite)
+    }
+}
+
+/// Splits markup into items for layout, measuring characters with `font` and icons from `style`.
+pub fn items<T: Texture>(font: &SpriteFont<T>, style: &MarkupStyle<T>, s: &str) -> Vec<TextItem> {
+    let tokens: Vec<_> = Tokens::new(s).collect();
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(i, &(start, end, token))| {
+            let (kind, width, advance) = match token {
+                Token::Char('\n') => (TextItemKind::Newline, 0, 0),
+                Token::Char(c) => {
+                    // kern against the next character, unless there's an icon in between
+                    let next = tokens[i + 1..]
+                        .iter()
+                        .find_map(|&(_, _, token)| match token {
+                            Token::Char(next) => Some(Some(next)),
+                            Token::Tag(Tag::Icon(_)) => Some(None),
+                            Token::Tag(_) => None,
+                        });
+                    let kind = if c == ' ' {
+                        TextItemKind::Space
+                    } else {
+                        TextItemKind::Glyph
+                    };
+                    (kind, font.glyph(c).width, font.advance(c, next.flatten()))
+                }
+                Token::Tag(Tag::Icon(name)) => match style.icon(name) {
+                    Some(sprite) => (
+                        TextItemKind::Glyph,
+                        sprite.width,
+                        (sprite.width + font.horizontal_padding) as i32,
+                    ),
+                    None => (TextItemKind::Control, 0, 0),
+                },
+                Token::Tag(_) => (TextItemKind::Control, 0, 0),
+            };
+            TextItem {
+                start,
+                end,
+                kind,
+                width,
+                advance,
+            }
+        })
+        .collect()
+}
+
+const WAVE_OFFSETS: [i32; 8] = [0, -1, -1, -1, 0, 1, 1, 1];
+
+/// The colors and effects in use at some point while drawing markup.
+pub struct MarkupState {
+    default_draw_colors: u16,
+    draw_colors: Vec<u16>,
+    wave: u32,
+    shake: u32,
+    char_count: u32,
+}
+
+impl MarkupState {
+    pub fn new(draw_colors: u16) -> Self {
+        Self {
+            default_draw_colors: draw_colors,
+            draw_colors: Vec::new(),
+            wave: 0,
+            shake: 0,
+            char_count: 0,
+        }
+    }
+
+    pub fn draw_colors(&self) -> u16 {
+        *self.draw_colors.last().unwrap_or(&self.default_draw_colors)
+    }
+
+    /// Updates the state for a tag, returning whether the draw colors changed.
+    pub fn apply(&mut self, tag: &Tag) -> bool {
+        let draw_colors = self.draw_colors();
+        match *tag {
+            // glyphs are drawn with the second draw color, with any outline or shadow in a 2bpp
+            // font using the third and fourth
+            Tag::Color(color) => self
+                .draw_colors
+                .push((draw_colors & !0x00f0) | (color as u16) << 4),
+            Tag::DrawColors(draw_colors) => self.draw_colors.push(draw_colors),
+            Tag::EndColor => {
+                self.draw_colors.pop();
+            }
+            Tag::Effect(Effect::Wave) => self.wave += 1,
+            Tag::EndEffect(Effect::Wave) => self.wave = self.wave.saturating_sub(1),
+            Tag::Effect(Effect::Shake) => self.shake += 1,
+            Tag::EndEffect(Effect::Shake) => self.shake = self.shake.saturating_sub(1),
+            Tag::Icon(_) => {}
+        }
+        draw_colors != self.draw_colors()
+    }
+
+    /// The offset to draw the next character or icon at, given the current effects.
+    pub fn next_offset(&mut self, frame: u32) -> (i32, i32) {
+        let i = self.char_count;
+        self.char_count += 1;
+        let mut offset = (0, 0);
+        if self.wave > 0 {
+            offset.1 += WAVE_OFFSETS[((frame / 4 + i) % WAVE_OFFSETS.len() as u32) as usize];
+        }
+        if self.shake > 0 {
+            let hash = (frame / 2)
+                .wrapping_mul(0x9e37_79b9)
+                .wrapping_add(i.wrapping_mul(0x85eb_ca6b))
+                .wrapping_mul(0xc2b2_ae35);
+            offset.0 += (hash >> 16) as i32 % 3 - 1;
+            offset.1 += (hash >> 24) as i32 % 3 - 1;
+        }
+        offset
+    }
+}
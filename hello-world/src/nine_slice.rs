@@ -0,0 +1,145 @@
+use crate::{
+    sprite::{Sprite, Texture},
+    wasm4, wasm4_mmio,
+};
+
+/// How the edges or center of a nine-slice fill the space between the corners.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SliceMode {
+    // repeats the piece, cutting off the last copy
+    Tile,
+    // scales the piece to fit, repeating or skipping pixels, which takes a blit per repeated pixel
+    Stretch,
+}
+
+/// A sprite split into a 3x3 grid of corners, edges and a center, which can be drawn as a panel
+/// of any size. The corners are always drawn as they are, and the edges and center are tiled or
+/// stretched to fill the rest. Panels smaller than the corners put together overlap them.
+pub struct NineSlice<'a, T: Texture> {
+    pub sprite: Sprite<'a, T>,
+    // widths of the left and right columns and heights of the top and bottom rows of the grid
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+    pub edges: SliceMode,
+    pub center: SliceMode,
+}
+
+/// A run of pixels copied from the source to the destination along one axis.
+struct Span {
+    src: u32,
+    dst: i32,
+    len: u32,
+}
+
+/// Splits `dst_len` pixels starting at `dst` into runs copied from the `src_len` pixels at `src`.
+fn spans(src: u32, src_len: u32, dst: i32, dst_len: u32, mode: SliceMode) -> Vec<Span> {
+    let mut spans = Vec::new();
+    if src_len == 0 || dst_len == 0 {
+        return spans;
+    }
+    match mode {
+        SliceMode::Tile => {
+            for offset in (0..dst_len).step_by(src_len as usize) {
+                spans.push(Span {
+                    src,
+                    dst: dst + offset as i32,
+                    len: src_len.min(dst_len - offset),
+                });
+            }
+        }
+        SliceMode::Stretch => {
+            for offset in 0..dst_len {
+                let src_offset = offset * src_len / dst_len;
+                match spans.last_mut() {
+                    // merge runs that copy consecutive source pixels
+                    Some(span) if span.src + span.len == src + src_offset => span.len += 1,
+                    _ => spans.push(Span {
+                        src: src + src_offset,
+                        dst: dst + offset as i32,
+                        len: 1,
+                    }),
+                }
+            }
+        }
+    }
+    spans
+}
+
+impl<'a, T: Texture> NineSlice<'a, T> {
+    /// Draws a panel with its top-left corner at `x`, `y`.
+    pub fn draw(&self, x: i32, y: i32, width: u32, height: u32) {
+        let sprite = &self.sprite;
+        let center_src_width = sprite.width.saturating_sub(self.left + self.right);
+        let center_src_height = sprite.height.saturating_sub(self.top + self.bottom);
+        let center_width = width.saturating_sub(self.left + self.right);
+        let center_height = height.saturating_sub(self.top + self.bottom);
+
+        // source and destination of each column and row of the grid, and how it fills the space
+        let columns = [
+            (0, self.left, x, self.left, SliceMode::Tile),
+            (
+                self.left,
+                center_src_width,
+                x + self.left as i32,
+                center_width,
+                self.edges,
+            ),
+            (
+                sprite.width.saturating_sub(self.right),
+                self.right,
+                x + width as i32 - self.right as i32,
+                self.right,
+                SliceMode::Tile,
+            ),
+        ];
+        let rows = [
+            (0, self.top, y, self.top, SliceMode::Tile),
+            (
+                self.top,
+                center_src_height,
+                y + self.top as i32,
+                center_height,
+                self.edges,
+            ),
+            (
+                sprite.height.saturating_sub(self.bottom),
+                self.bottom,
+                y + height as i32 - self.bottom as i32,
+                self.bottom,
+                SliceMode::Tile,
+            ),
+        ];
+
+        wasm4_mmio::DRAW_COLORS.write(sprite.draw_colors);
+        for (row, &(src_y, src_height, dst_y, dst_height, row_mode)) in rows.iter().enumerate() {
+            for (column, &(src_x, src_width, dst_x, dst_width, column_mode)) in
+                columns.iter().enumerate()
+            {
+                let (column_mode, row_mode) = if row == 1 && column == 1 {
+                    (self.center, self.center)
+                } else {
+                    (column_mode, row_mode)
+                };
+                let x_spans = spans(src_x, src_width, dst_x, dst_width, column_mode);
+                let y_spans = spans(src_y, src_height, dst_y, dst_height, row_mode);
+                for y_span in &y_spans {
+                    for x_span in &x_spans {
+                        wasm4::blit_sub(
+                            sprite.texture.data(),
+                            x_span.dst,
+                            y_span.dst,
+                            x_span.len,
+                            y_span.len,
+                            sprite.src_x + x_span.src,
+                            sprite.src_y + y_span.src,
+                            sprite.texture.stride(),
+                            T::BLIT_FORMAT,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
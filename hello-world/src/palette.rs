@@ -0,0 +1,245 @@
+use crate::wasm4_mmio::PALETTE;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Palette {
+    pub name: &'static str,
+    // 0xRRGGBB colors for palette slots 0 to 3
+    pub colors: [u32; 4],
+}
+
+pub const BLACK: Palette = Palette {
+    name: "black",
+    colors: [0x000000; 4],
+};
+pub const WHITE: Palette = Palette {
+    name: "white",
+    colors: [0xffffff; 4],
+};
+
+/// Blends two 0xRRGGBB colors, `frame` frames into a fade lasting `frames` frames.
+fn lerp_color(from: u32, to: u32, frame: u32, frames: u32) -> u32 {
+    let mut color = 0;
+    for shift in [0, 8, 16] {
+        let (from, to) = ((from >> shift) & 0xff, (to >> shift) & 0xff);
+        let channel =
+            (from as i32 + (to as i32 - from as i32) * frame as i32 / frames as i32) as u32;
+        color |= channel << shift;
+    }
+    color
+}
+
+struct Fade {
+    from: [u32; 4],
+    to: [u32; 4],
+    frame: u32,
+    frames: u32,
+}
+
+struct ColorCycle {
+    slot: usize,
+    colors: &'static [u32],
+    frames_per_color: u32,
+    frame: u32,
+}
+
+struct Flash {
+    colors: [u32; 4],
+    frames_remaining: u32,
+}
+
+/// Keeps track of the colors WASM-4 draws with, fading between palettes, cycling the colors of
+/// individual slots and flashing the whole screen. Call `update` once per frame to write the
+/// result to `PALETTE`.
+pub struct PaletteManager {
+    palettes: &'static [Palette],
+    colors: [u32; 4],
+    fade: Option<Fade>,
+    cycles: Vec<ColorCycle>,
+    flash: Option<Flash>,
+    // the colors last written to PALETTE, so unchanged frames can skip writing them
+    written: Option<[u32; 4]>,
+}
+
+impl PaletteManager {
+    /// Starts out showing the first of `palettes`.
+    pub fn new(palettes: &'static [Palette]) -> Self {
+        Self {
+            palettes,
+            colors: palettes
+                .first()
+                .map_or(BLACK.colors, |palette| palette.colors),
+            fade: None,
+            cycles: Vec::new(),
+            flash: None,
+            written: None,
+        }
+    }
+
+    pub fn palette(&self, name: &str) -> Option<&'static Palette> {
+        self.palettes.iter().find(|palette| palette.name == name)
+    }
+
+    /// The colors currently shown, before any cycling or flash.
+    pub fn colors(&self) -> [u32; 4] {
+        self.colors
+    }
+
+    /// Switches to the named palette immediately, returning false if there is no such palette.
+    pub fn set(&mut self, name: &str) -> bool {
+        match self.palette(name) {
+            Some(palette) => {
+                self.set_colors(palette.colors);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Switches to `colors` immediately, cancelling any fade.
+    pub fn set_colors(&mut self, colors: [u32; 4]) {
+        self.colors = colors;
+        self.fade = None;
+    }
+
+    /// Fades from the current colors to the named palette over `frames` frames, returning false
+    /// if there is no such palette.
+    pub fn fade_to(&mut self, name: &str, frames: u32) -> bool {
+        match self.palette(name) {
+            Some(palette) => {
+                self.fade_to_colors(palette.colors, frames);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fades from the current colors to `colors` over `frames` frames, leaving any fade already
+    /// heading to `colors` alone.
+    pub fn fade_to_colors(&mut self, colors: [u32; 4], frames: u32) {
+        let target = self.fade.as_ref().map_or(self.colors, |fade| fade.to);
+        if target != colors {
+            self.fade_between(self.colors, colors, frames);
+        }
+    }
+
+    pub fn fade_to_black(&mut self, frames: u32) {
+        self.fade_to_colors(BLACK.colors, frames);
+    }
+
+    pub fn fade_to_white(&mut self, frames: u32) {
+        self.fade_to_colors(WHITE.colors, frames);
+    }
+
+    /// Cuts to black, then fades in to the named palette over `frames` frames.
+    pub fn fade_from_black(&mut self, name: &str, frames: u32) -> bool {
+        self.fade_from(BLACK.colors, name, frames)
+    }
+
+    /// Cuts to white, then fades in to the named palette over `frames` frames.
+    pub fn fade_from_white(&mut self, name: &str, frames: u32) -> bool {
+        self.fade_from(WHITE.colors, name, frames)
+    }
+
+    fn fade_from(&mut self, from: [u32; 4], name: &str, frames: u32) -> bool {
+        match self.palette(name) {
+            Some(palette) => {
+                self.fade_between(from, palette.colors, frames);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn fade_between(&mut self, from: [u32; 4], to: [u32; 4], frames: u32) {
+        if frames == 0 {
+            self.set_colors(to);
+            return;
+        }
+        self.colors = from;
+        self.fade = Some(Fade {
+            from,
+            to,
+            frame: 0,
+            frames,
+        });
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.fade.is_some()
+    }
+
+    /// Cycles palette slot `slot` through `colors`, showing each for `frames_per_color` frames,
+    /// replacing any cycle already running on that slot.
+    pub fn cycle(&mut self, slot: usize, colors: &'static [u32], frames_per_color: u32) {
+        self.stop_cycle(slot);
+        if !colors.is_empty() {
+            self.cycles.push(ColorCycle {
+                slot,
+                colors,
+                frames_per_color: frames_per_color.max(1),
+                frame: 0,
+            });
+        }
+    }
+
+    /// Stops cycling slot `slot`, returning it to the color from the current palette.
+    pub fn stop_cycle(&mut self, slot: usize) {
+        self.cycles.retain(|cycle| cycle.slot != slot);
+    }
+
+    /// Shows `colors` in place of the palette for the next `frames` frames, such as white for a
+    /// hit flash.
+    pub fn flash(&mut self, colors: [u32; 4], frames: u32) {
+        self.flash = (frames > 0).then_some(Flash {
+            colors,
+            frames_remaining: frames,
+        });
+    }
+
+    pub fn is_flashing(&self) -> bool {
+        self.flash.is_some()
+    }
+
+    /// The colors to show this frame, with cycling and any flash applied.
+    pub fn displayed_colors(&self) -> [u32; 4] {
+        if let Some(flash) = &self.flash {
+            return flash.colors;
+        }
+        let mut colors = self.colors;
+        for cycle in &self.cycles {
+            if let Some(color) = colors.get_mut(cycle.slot) {
+                *color = cycle.colors[(cycle.frame / cycle.frames_per_color) as usize];
+            }
+        }
+        colors
+    }
+
+    /// Writes this frame's colors to `PALETTE`, then advances fades, cycles and flashes by one
+    /// frame. Call once per frame.
+    pub fn update(&mut self) {
+        let colors = self.displayed_colors();
+        if self.written != Some(colors) {
+            PALETTE.write(colors);
+            self.written = Some(colors);
+        }
+
+        if let Some(fade) = &mut self.fade {
+            fade.frame += 1;
+            for (i, color) in self.colors.iter_mut().enumerate() {
+                *color = lerp_color(fade.from[i], fade.to[i], fade.frame, fade.frames);
+            }
+            if fade.frame >= fade.frames {
+                self.fade = None;
+            }
+        }
+        for cycle in &mut self.cycles {
+            cycle.frame = (cycle.frame + 1) % (cycle.colors.len() as u32 * cycle.frames_per_color);
+        }
+        if let Some(flash) = &mut self.flash {
+            flash.frames_remaining = flash.frames_remaining.saturating_sub(1);
+            if flash.frames_remaining == 0 {
+                self.flash = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use crate::framebuffer::Surface;
+
+// thresholds for an 8x8 ordered dither, from 0 to 63
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// An 8x8 mask repeated across a surface, one byte per row with the leftmost pixel in the
+/// highest bit. Patterns line up with the surface's own coordinates rather than the shape
+/// being drawn, so neighbouring shapes drawn with the same pattern join up seamlessly.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Pattern(pub [u8; 8]);
+
+impl Pattern {
+    pub const SOLID: Self = Self([0xff; 8]);
+    pub const EMPTY: Self = Self([0x00; 8]);
+    pub const CHECKERBOARD: Self = Self([0xaa, 0x55, 0xaa, 0x55, 0xaa, 0x55, 0xaa, 0x55]);
+    pub const HORIZONTAL_STRIPES: Self = Self([0xff, 0x00, 0xff, 0x00, 0xff, 0x00, 0xff, 0x00]);
+    pub const VERTICAL_STRIPES: Self = Self([0xaa; 8]);
+    pub const DIAGONAL_STRIPES: Self = Self([0x88, 0x44, 0x22, 0x11, 0x88, 0x44, 0x22, 0x11]);
+
+    /// An ordered dither with `level` of its 64 pixels set, from 0 for empty to 64 for solid.
+    pub const fn bayer(level: u32) -> Self {
+        let mut rows = [0; 8];
+        let mut y = 0;
+        while y < 8 {
+            let mut x = 0;
+            while x < 8 {
+                if (BAYER_8X8[y][x] as u32) < level {
+                    rows[y] |= 0x80 >> x;
+                }
+                x += 1;
+            }
+            y += 1;
+        }
+        Self(rows)
+    }
+
+    /// Whether the pattern is set at a point on the surface.
+    pub fn is_set(&self, x: i32, y: i32) -> bool {
+        self.0[y.rem_euclid(8) as usize] & (0x80 >> x.rem_euclid(8)) != 0
+    }
+}
+
+/// How to fill a shape: pixels where the pattern is set get `color`, and the rest get
+/// `background`, or are left alone if it's `None` so whatever is underneath shows through.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Fill {
+    pub pattern: Pattern,
+    pub color: u8,
+    pub background: Option<u8>,
+}
+
+impl Fill {
+    pub const fn solid(color: u8) -> Self {
+        Self {
+            pattern: Pattern::SOLID,
+            color,
+            background: None,
+        }
+    }
+
+    pub const fn pattern(pattern: Pattern, color: u8) -> Self {
+        Self {
+            pattern,
+            color,
+            background: None,
+        }
+    }
+
+    pub fn color_at(&self, x: i32, y: i32) -> Option<u8> {
+        if self.pattern.is_set(x, y) {
+            Some(self.color)
+        } else {
+            self.background
+        }
+    }
+
+    pub fn set_pixel(&self, surface: &mut (impl Surface + ?Sized), x: i32, y: i32) {
+        if let Some(color) = self.color_at(x, y) {
+            surface.set_pixel(x, y, color);
+        }
+    }
+
+    pub fn hline(&self, surface: &mut (impl Surface + ?Sized), x: i32, y: i32, len: u32) {
+        if self.pattern == Pattern::SOLID {
+            surface.hline(x, y, len, self.color);
+            return;
+        }
+        for x in x.max(0)..(x + len as i32).min(surface.width() as i32) {
+            self.set_pixel(surface, x, y);
+        }
+    }
+
+    pub fn vline(&self, surface: &mut (impl Surface + ?Sized), x: i32, y: i32, len: u32) {
+        for y in y.max(0)..(y + len as i32).min(surface.height() as i32) {
+            self.set_pixel(surface, x, y);
+        }
+    }
+
+    pub fn rect(
+        &self,
+        surface: &mut (impl Surface + ?Sized),
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) {
+        for y in y.max(0)..(y + height as i32).min(surface.height() as i32) {
+            self.hline(surface, x, y, width);
+        }
+    }
+
+    /// Fills the ellipse that fits inside the given rectangle.
+    pub fn oval(
+        &self,
+        surface: &mut (impl Surface + ?Sized),
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) {
+        let (radius_x, radius_y) = (width as f32 / 2.0, height as f32 / 2.0);
+        for row in 0..height as i32 {
+            // measure each row through the middle of its pixels
+            let dy = (row as f32 + 0.5 - radius_y) / radius_y;
+            let half_width = radius_x * (1.0 - dy * dy).max(0.0).sqrt();
+            let start = (radius_x - half_width).round() as i32;
+            let end = (radius_x + half_width).round() as i32;
+            if end > start {
+                self.hline(surface, x + start, y + row, (end - start) as u32);
+            }
+        }
+    }
+
+    /// Draws a one pixel wide line between two points, inclusive.
+    pub fn line(&self, surface: &mut (impl Surface + ?Sized), x1: i32, y1: i32, x2: i32, y2: i32) {
+        // only the part on the surface is stepped through, however far away the ends are
+        let max = (surface.width() as i64 - 1, surface.height() as i64 - 1);
+        let Some(((x1, y1), (x2, y2))) =
+            clip_line((x1 as i64, y1 as i64), (x2 as i64, y2 as i64), max)
+        else {
+            return;
+        };
+        // Bresenham's line algorithm
+        let (dx, dy) = ((x2 - x1).abs(), -(y2 - y1).abs());
+        let (step_x, step_y) = ((x2 - x1).signum(), (y2 - y1).signum());
+        let (mut x, mut y, mut error) = (x1, y1, dx + dy);
+        loop {
+            self.set_pixel(surface, x, y);
+            if x == x2 && y == y2 {
+                break;
+            }
+            let doubled = error * 2;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+}
+
+// which sides of the clipping rectangle a point is beyond, for `clip_line`
+const OUTSIDE_LEFT: u8 = 1;
+const OUTSIDE_RIGHT: u8 = 2;
+const OUTSIDE_TOP: u8 = 4;
+const OUTSIDE_BOTTOM: u8 = 8;
+
+/// `n / d` rounded to the nearest whole number.
+fn div_round(n: i128, d: i128) -> i128 {
+    let (n, d) = if d < 0 { (-n, -d) } else { (n, d) };
+    (2 * n + d).div_euclid(2 * d)
+}
+
+/// Clips the line between two points to the rectangle from 0, 0 to `max` inclusive with the
+/// Cohen–Sutherland algorithm, returning the ends of the part inside it, if there is one.
+fn clip_line(
+    mut p1: (i64, i64),
+    mut p2: (i64, i64),
+    max: (i64, i64),
+) -> Option<((i32, i32), (i32, i32))> {
+    if max.0 < 0 || max.1 < 0 {
+        return None;
+    }
+    let outcode = |(x, y): (i64, i64)| {
+        let mut code = 0;
+        if x < 0 {
+            code |= OUTSIDE_LEFT;
+        } else if x > max.0 {
+            code |= OUTSIDE_RIGHT;
+        }
+        if y < 0 {
+            code |= OUTSIDE_TOP;
+        } else if y > max.1 {
+            code |= OUTSIDE_BOTTOM;
+        }
+        code
+    };
+    // each end is moved onto at most two edges, but rounding can take a few more tries
+    for _ in 0..8 {
+        let (code1, code2) = (outcode(p1), outcode(p2));
+        if code1 | code2 == 0 {
+            return Some(((p1.0 as i32, p1.1 as i32), (p2.0 as i32, p2.1 as i32)));
+        }
+        if code1 & code2 != 0 {
+            return None;
+        }
+        // the deltas can need 33 bits and their products 66, so work in i128
+        let (x1, y1, x2, y2) = (p1.0 as i128, p1.1 as i128, p2.0 as i128, p2.1 as i128);
+        let (code, point) = if code1 != 0 {
+            (code1, &mut p1)
+        } else {
+            (code2, &mut p2)
+        };
+        let at_y = |y: i128| (x1 + div_round((x2 - x1) * (y - y1), y2 - y1), y);
+        let at_x = |x: i128| (x, y1 + div_round((y2 - y1) * (x - x1), x2 - x1));
+        let (x, y) = if code & OUTSIDE_TOP != 0 {
+            at_y(0)
+        } else if code & OUTSIDE_BOTTOM != 0 {
+            at_y(max.1 as i128)
+        } else if code & OUTSIDE_LEFT != 0 {
+            at_x(0)
+        } else {
+            at_x(max.0 as i128)
+        };
+        *point = (x as i64, y as i64);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framebuffer::Framebuffer;
+
+    fn surface() -> Framebuffer<Vec<u8>> {
+        Framebuffer::new(vec![0; 16 * 16 / 4], 16, 16)
+    }
+
+    fn set_pixels(surface: &Framebuffer<Vec<u8>>) -> Vec<(i32, i32)> {
+        let mut pixels = Vec::new();
+        for y in 0..16 {
+            for x in 0..16 {
+                if surface.get_pixel(x, y) != Some(0) {
+                    pixels.push((x, y));
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn lines_on_the_surface_are_drawn_whole() {
+        let mut s = surface();
+        Fill::solid(1).line(&mut s, 1, 1, 5, 3);
+        assert_eq!(set_pixels(&s), [(1, 1), (2, 2), (3, 2), (4, 3), (5, 3)]);
+    }
+
+    #[test]
+    fn lines_are_clipped_to_the_surface() {
+        let mut s = surface();
+        Fill::solid(1).line(&mut s, -10, 4, 30, 4);
+        assert_eq!(set_pixels(&s), (0..16).map(|x| (x, 4)).collect::<Vec<_>>());
+
+        let mut s = surface();
+        Fill::solid(1).line(&mut s, -8, -8, 100, 100);
+        assert_eq!(set_pixels(&s), (0..16).map(|i| (i, i)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn lines_missing_the_surface_draw_nothing() {
+        let mut s = surface();
+        Fill::solid(1).line(&mut s, -5, 20, 40, 100);
+        Fill::solid(1).line(&mut s, 20, -5, 30, -1);
+        // crosses the corner's diagonal without touching the surface
+        Fill::solid(1).line(&mut s, -10, 8, 8, -10);
+        assert!(set_pixels(&s).is_empty());
+    }
+
+    #[test]
+    fn extreme_ends_dont_overflow() {
+        let mut s = surface();
+        Fill::solid(1).line(&mut s, i32::MIN, i32::MIN, i32::MAX, i32::MAX);
+        assert_eq!(set_pixels(&s), (0..16).map(|i| (i, i)).collect::<Vec<_>>());
+
+        let mut s = surface();
+        Fill::solid(1).line(&mut s, i32::MIN, 3, i32::MAX, 3);
+        assert_eq!(set_pixels(&s).len(), 16);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{
+    framebuffer::{pixel_index, Surface},
+    sprite::Texture4Color,
+    wasm4::{self, BLIT_2BPP},
+    wasm4_mmio,
+};
+
+// maps each pixel value to the palette color with the same index
+const IDENTITY_DRAW_COLORS: u16 = 0x4321;
+
+// where pixel `i` is in its byte, the opposite way round to `Framebuffer`, since `blit` reads
+// the leftmost pixel from the highest bits
+fn shift(i: usize) -> usize {
+    6 - (i % 4) * 2
+}
+
+/// An offscreen image in main memory that can be drawn on like the framebuffer, then drawn to the
+/// screen with `blit`. Pixels are packed 4 to a byte like the framebuffer, but with the leftmost
+/// pixel in the highest bits of each byte, which is the 2bpp format that `blit` expects.
+pub struct RenderTarget<B: AsRef<[u8]> + AsMut<[u8]> = Vec<u8>> {
+    bytes: B,
+    width: u32,
+    height: u32,
+}
+
+impl RenderTarget {
+    /// Allocates a render target on the heap, filled with color 0.
+    pub fn new(width: u32, height: u32) -> Self {
+        let len = ((width * height) as usize).div_ceil(4);
+        Self::with_bytes(vec![0; len], width, height)
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> RenderTarget<B> {
+    /// Uses `bytes`, which must have room for `width * height` pixels, as the render target's
+    /// storage, such as a static buffer.
+    pub fn with_bytes(bytes: B, width: u32, height: u32) -> Self {
+        assert!(bytes.as_ref().len() * 4 >= (width * height) as usize);
+        Self {
+            bytes,
+            width,
+            height,
+        }
+    }
+
+    pub fn texture(&self) -> Texture4Color<'_> {
+        Texture4Color {
+            data: self.bytes.as_ref(),
+            stride: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Draws the whole render target with each pixel in its own palette color.
+    pub fn draw(&self, x: i32, y: i32, flags: u32) {
+        wasm4_mmio::DRAW_COLORS.write(IDENTITY_DRAW_COLORS);
+        wasm4::blit(
+            self.bytes.as_ref(),
+            x,
+            y,
+            self.width,
+            self.height,
+            flags | BLIT_2BPP,
+        );
+    }
+
+    /// Copies the area of `source` starting at `x`, `y` into the render target, such as to
+    /// capture part of the framebuffer after drawing to it.
+    pub fn copy_from(&mut self, source: &dyn Surface, x: i32, y: i32) {
+        for dst_y in 0..self.height as i32 {
+            for dst_x in 0..self.width as i32 {
+                if let Some(color) = source.get_pixel(x + dst_x, y + dst_y) {
+                    self.set_pixel(dst_x, dst_y, color);
+                }
+            }
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> Surface for RenderTarget<B> {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn get_pixel(&self, x: i32, y: i32) -> Option<u8> {
+        let i = pixel_index(self.width, self.height, x, y)?;
+        Some((self.bytes.as_ref()[i / 4] >> shift(i)) & 0b11)
+    }
+
+    fn set_pixel(&mut self, x: i32, y: i32, color: u8) {
+        if let Some(i) = pixel_index(self.width, self.height, x, y) {
+            let shift = shift(i);
+            let byte = &mut self.bytes.as_mut()[i / 4];
+            *byte = (*byte & !(0b11 << shift)) | ((color & 0b11) << shift);
+        }
+    }
+
+    fn clear(&mut self, color: u8) {
+        self.bytes.as_mut().fill((color & 0b11) * 0b0101_0101);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{framebuffer::Framebuffer, sprite::Texture, wasm4::SCREEN_SIZE};
+
+    // what `draw` does, for a texture drawn with each pixel value mapped through `draw_colors`
+    // like `blit`, where 0 in a draw color means transparent
+    fn blit(fb: &mut Framebuffer<Vec<u8>>, target: &RenderTarget, draw_colors: u16) {
+        let texture = target.texture();
+        for y in 0..target.height() {
+            for x in 0..target.width() {
+                let draw_color = (draw_colors >> (texture.pixel(x, y) * 4)) & 0xf;
+                if draw_color != 0 {
+                    fb.set_pixel(x as i32, y as i32, draw_color as u8 - 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pixels_are_packed_leftmost_in_the_highest_bits() {
+        let mut target = RenderTarget::new(8, 2);
+        for (x, color) in [(0, 1), (1, 2), (2, 3), (3, 1)] {
+            target.set_pixel(x, 0, color);
+        }
+        assert_eq!(target.texture().data[0], 0b01_10_11_01);
+        for (x, color) in [(0, 1), (1, 2), (2, 3), (3, 1)] {
+            assert_eq!(target.get_pixel(x, 0), Some(color));
+        }
+        assert_eq!(target.get_pixel(4, 0), Some(0));
+    }
+
+    #[test]
+    fn set_pixel_leaves_the_rest_of_the_byte_alone() {
+        let mut target = RenderTarget::new(8, 2);
+        target.clear(3);
+        for (x, shift) in [(4, 6), (5, 4), (6, 2), (7, 0)] {
+            target.set_pixel(x, 1, 0);
+            assert_eq!(target.texture().data[3], !(0b11 << shift));
+            target.set_pixel(x, 1, 3);
+        }
+        assert!(target.texture().data.iter().all(|&byte| byte == 0xff));
+    }
+
+    #[test]
+    fn hline_across_byte_boundaries() {
+        let mut target = RenderTarget::new(16, 3);
+        target.hline(3, 2, 10, 2);
+        assert_eq!(
+            target.texture().data[8..12],
+            [0b00_00_00_10, 0xaa, 0xaa, 0b10_00_00_00]
+        );
+        for x in 0..16 {
+            let expected = if (3..13).contains(&x) { 2 } else { 0 };
+            assert_eq!(target.get_pixel(x, 2), Some(expected), "x = {x}");
+        }
+    }
+
+    #[test]
+    fn pixels_outside_are_ignored() {
+        let mut target = RenderTarget::new(8, 2);
+        target.set_pixel(-1, 0, 3);
+        target.set_pixel(8, 0, 3);
+        target.set_pixel(0, 2, 3);
+        target.hline(-4, 1, 2, 3);
+        target.fill_rect(6, -3, 5, 3, 3);
+        assert!(target.texture().data.iter().all(|&byte| byte == 0));
+        assert_eq!(target.get_pixel(-1, 0), None);
+        assert_eq!(target.get_pixel(0, 2), None);
+    }
+
+    #[test]
+    fn blitting_back_with_identity_draw_colors_restores_the_screen() {
+        let mut fb = Framebuffer::new(vec![0; 6400], SCREEN_SIZE, SCREEN_SIZE);
+        for y in 0..SCREEN_SIZE as i32 {
+            for x in 0..SCREEN_SIZE as i32 {
+                fb.set_pixel(x, y, ((x * 7 + y * 3) % 4) as u8);
+            }
+        }
+        let mut target = RenderTarget::new(SCREEN_SIZE, SCREEN_SIZE);
+        target.copy_from(&fb, 0, 0);
+
+        let mut screen = Framebuffer::new(vec![0; 6400], SCREEN_SIZE, SCREEN_SIZE);
+        blit(&mut screen, &target, IDENTITY_DRAW_COLORS);
+        assert_eq!(screen.bytes(), fb.bytes());
+    }
+
+    #[test]
+    fn copy_from_an_offset_leaves_pixels_outside_the_source() {
+        let mut fb = Framebuffer::new(vec![0; 4], 4, 4);
+        fb.clear(2);
+        let mut target = RenderTarget::new(4, 4);
+        target.clear(1);
+        target.copy_from(&fb, 2, 1);
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if x < 2 && y < 3 { 2 } else { 1 };
+                assert_eq!(target.get_pixel(x, y), Some(expected), "{x}, {y}");
+            }
+        }
+    }
+}
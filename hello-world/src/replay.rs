@@ -0,0 +1,311 @@
+use crate::{input::InputFrame, wasm4};
+
+// which of a frame's fields changed since the previous run, in a run's mask byte
+const GAMEPAD_CHANGED: [u8; 4] = [1 << 0, 1 << 1, 1 << 2, 1 << 3];
+const MOUSE_X_CHANGED: u8 = 1 << 4;
+const MOUSE_Y_CHANGED: u8 = 1 << 5;
+const MOUSE_BUTTONS_CHANGED: u8 = 1 << 6;
+// bytes of hex per line when exporting with `trace`
+const TRACE_LINE_LEN: usize = 64;
+
+/// Records the input of every frame into a compact stream, which `InputReplay` plays back. The
+/// stream is a series of runs of identical frames, each a byte with the number of frames in it,
+/// a byte with a bit set for each field that differs from the run before, and then the new values
+/// of those fields: the four gamepads, then the mouse's x and y as little-endian `i16`s, then its
+/// buttons. Before the first run, every field is 0.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct InputRecorder {
+    data: Vec<u8>,
+    last: InputFrame,
+    // where the current run's frame count is in `data`
+    run_start: usize,
+    frames: u32,
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a frame's input to the end of the recording.
+    pub fn record(&mut self, frame: &InputFrame) {
+        if self.frames > 0 && *frame == self.last && self.data[self.run_start] < u8::MAX {
+            self.data[self.run_start] += 1;
+            self.frames += 1;
+            return;
+        }
+
+        self.run_start = self.data.len();
+        self.data.push(1);
+        let mask_index = self.data.len();
+        self.data.push(0);
+        let mut mask = 0;
+        for (i, (&bits, &last_bits)) in frame.gamepads.iter().zip(&self.last.gamepads).enumerate() {
+            if bits != last_bits {
+                mask |= GAMEPAD_CHANGED[i];
+                self.data.push(bits);
+            }
+        }
+        if frame.mouse_x != self.last.mouse_x {
+            mask |= MOUSE_X_CHANGED;
+            self.data.extend_from_slice(&frame.mouse_x.to_le_bytes());
+        }
+        if frame.mouse_y != self.last.mouse_y {
+            mask |= MOUSE_Y_CHANGED;
+            self.data.extend_from_slice(&frame.mouse_y.to_le_bytes());
+        }
+        if frame.mouse_buttons != self.last.mouse_buttons {
+            mask |= MOUSE_BUTTONS_CHANGED;
+            self.data.push(frame.mouse_buttons);
+        }
+        self.data[mask_index] = mask;
+        self.last = *frame;
+        self.frames += 1;
+    }
+
+    /// The recording so far, for a host harness to save or for `InputReplay` to play back.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// How many frames have been recorded.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Prints the recording to the debug console as hex, a line at a time, which `decode_hex` can
+    /// turn back into bytes, such as for a test fixture.
+    pub fn trace(&self) {
+        wasm4::trace(format!("input recording, {} frames:", self.frames));
+        for line in self.data.chunks(TRACE_LINE_LEN) {
+            let hex: String = line.iter().map(|byte| format!("{byte:02x}")).collect();
+            wasm4::trace(hex);
+        }
+    }
+}
+
+/// Turns hex printed by `InputRecorder::trace` back into bytes, ignoring whitespace between
+/// lines. Returns `None` if there's anything else in `text`.
+pub fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks_exact(2)
+        .map(|pair| u8::from_str_radix(core::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Plays back a recording made by `InputRecorder`, one frame at a time. Feeding each frame to
+/// `Input::update_with` gives the game exactly the input it had when it was recorded, so as long
+/// as it doesn't depend on anything else, such as the time, it plays out the same way, which
+/// makes recordings usable as regression tests run without WASM-4.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InputReplay<'a> {
+    data: &'a [u8],
+    // where the next run starts in `data`
+    position: usize,
+    frame: InputFrame,
+    // frames left in the current run
+    remaining: u8,
+    corrupt: bool,
+}
+
+impl<'a> InputReplay<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            frame: InputFrame::default(),
+            remaining: 0,
+            corrupt: false,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.remaining == 0 && self.position >= self.data.len()
+    }
+
+    /// Whether playback stopped early because the recording is cut off partway through a run or
+    /// has a run of no frames, as can happen to one that's been edited by hand.
+    pub fn is_corrupt(&self) -> bool {
+        self.corrupt
+    }
+
+    fn read_byte(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.position)?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn read_i16(&mut self) -> Option<i16> {
+        Some(i16::from_le_bytes([self.read_byte()?, self.read_byte()?]))
+    }
+
+    /// Reads the next run's length and the fields it changes, returning `None` if it's corrupt.
+    fn read_run(&mut self) -> Option<()> {
+        self.remaining = self.read_byte().filter(|&frames| frames > 0)?;
+        let mask = self.read_byte()?;
+        for (i, changed) in GAMEPAD_CHANGED.into_iter().enumerate() {
+            if mask & changed != 0 {
+                self.frame.gamepads[i] = self.read_byte()?;
+            }
+        }
+        if mask & MOUSE_X_CHANGED != 0 {
+            self.frame.mouse_x = self.read_i16()?;
+        }
+        if mask & MOUSE_Y_CHANGED != 0 {
+            self.frame.mouse_y = self.read_i16()?;
+        }
+        if mask & MOUSE_BUTTONS_CHANGED != 0 {
+            self.frame.mouse_buttons = self.read_byte()?;
+        }
+        Some(())
+    }
+}
+
+impl Iterator for InputReplay<'_> {
+    type Item = InputFrame;
+
+    /// The next frame's input, or `None` once the recording has finished or turns out to be
+    /// corrupt.
+    fn next(&mut self) -> Option<InputFrame> {
+        if self.remaining == 0 {
+            if self.position >= self.data.len() {
+                return None;
+            }
+            if self.read_run().is_none() {
+                self.corrupt = true;
+                self.remaining = 0;
+                self.position = self.data.len();
+                return None;
+            }
+        }
+        self.remaining -= 1;
+        Some(self.frame)
+    }
+}
+
+/// Where each frame's input comes from: the registers, the registers while recording them, or a
+/// replay, which goes back to the registers once it's finished.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum InputSource<'a> {
+    Live,
+    Recording(InputRecorder),
+    Replaying(InputReplay<'a>),
+}
+
+impl InputSource<'_> {
+    /// This frame's input, to pass to `Input::update_with`.
+    pub fn next_frame(&mut self) -> InputFrame {
+        match self {
+            InputSource::Live => InputFrame::read(),
+            InputSource::Recording(recorder) => {
+                let frame = InputFrame::read();
+                recorder.record(&frame);
+                frame
+            }
+            InputSource::Replaying(replay) => replay.next().unwrap_or_else(InputFrame::read),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Button, Input, MouseButton};
+
+    fn frame(gamepad1: u8, mouse_x: i16, mouse_y: i16, mouse_buttons: u8) -> InputFrame {
+        InputFrame {
+            gamepads: [gamepad1, 0, 0, 0],
+            mouse_x,
+            mouse_y,
+            mouse_buttons,
+        }
+    }
+
+    #[test]
+    fn replays_what_was_recorded() {
+        let mut frames = vec![frame(0, 0, 0, 0); 300];
+        frames.extend([frame(1, 0, 0, 0); 3]);
+        frames.push(frame(1, -5, -300, 1));
+        frames.extend([frame(0, 200, i16::MIN, 0); 600]);
+        frames.push(InputFrame {
+            gamepads: [16, 32, 64, 128],
+            mouse_x: i16::MAX,
+            mouse_y: -1,
+            mouse_buttons: 4,
+        });
+
+        let mut recorder = InputRecorder::new();
+        for frame in &frames {
+            recorder.record(frame);
+        }
+        assert_eq!(recorder.frames(), frames.len() as u32);
+        let mut replay = InputReplay::new(recorder.data());
+        let replayed: Vec<InputFrame> = replay.by_ref().collect();
+        assert_eq!(replayed, frames);
+        assert!(replay.is_finished());
+        assert!(!replay.is_corrupt());
+    }
+
+    #[test]
+    fn fixture_drives_input() {
+        // 3 idle frames, X held on gamepad 1 for 2 frames, then a left click at 10, 20
+        let fixture = "
+            0300 020101 010100
+            0170 0a00 1400 01
+            014000
+        ";
+        let data = decode_hex(fixture).unwrap();
+        let mut input = Input::new();
+        let mut events = Vec::new();
+        for (i, frame) in InputReplay::new(&data).enumerate() {
+            input.update_with(&frame);
+            if input.gamepad(0).pressed(Button::X) {
+                events.push((i, "pressed X"));
+            }
+            if input.gamepad(0).released(Button::X) {
+                events.push((i, "released X"));
+            }
+            if input.mouse().clicked(MouseButton::Left) {
+                assert_eq!(input.mouse().position(), (10, 20));
+                events.push((i, "clicked"));
+            }
+        }
+        assert_eq!(
+            events,
+            [(3, "pressed X"), (5, "released X"), (7, "clicked")]
+        );
+    }
+
+    #[test]
+    fn corrupt_recordings_stop_early() {
+        // a run of no frames
+        let mut replay = InputReplay::new(&[0x02, 0x00, 0x00, 0x00]);
+        assert_eq!(replay.by_ref().count(), 2);
+        assert!(replay.is_corrupt());
+
+        // cut off in the middle of the mouse's x
+        let mut replay = InputReplay::new(&[0x01, 0x01, 0x03, 0x01, 0x10, 0x05]);
+        assert_eq!(replay.next(), Some(frame(3, 0, 0, 0)));
+        assert_eq!(replay.next(), None);
+        assert!(replay.is_corrupt());
+        assert_eq!(replay.next(), None);
+    }
+
+    #[test]
+    fn decode_hex_rejects_bad_input() {
+        assert_eq!(decode_hex("0a f"), None);
+        assert_eq!(decode_hex("zz"), None);
+        assert_eq!(decode_hex(" 0a\nFF "), Some(vec![0x0a, 0xff]));
+    }
+}
@@ -0,0 +1,110 @@
+/// Unpacks run-length encoded bytes from the asset pipeline into `dest`, returning how many
+/// bytes were written. Each control byte below 0x80 is followed by that many plus one bytes to
+/// copy, and each control byte of 0x80 or above by one byte to repeat the control byte minus
+/// 0x7d times. Panics if `dest` is too small.
+pub fn rle_decode(src: &[u8], dest: &mut [u8]) -> usize {
+    let (mut i, mut len) = (0, 0);
+    while i < src.len() {
+        let control = src[i] as usize;
+        if control < 0x80 {
+            let count = control + 1;
+            dest[len..len + count].copy_from_slice(&src[i + 1..i + 1 + count]);
+            i += 1 + count;
+            len += count;
+        } else {
+            let count = control - 0x7d;
+            dest[len..len + count].fill(src[i + 1]);
+            i += 2;
+            len += count;
+        }
+    }
+    len
+}
+
+/// Run-length encodes `data` in the format `rle_decode` reads, for the asset pipeline in
+/// `build.rs`. Runs of 3 or more bytes become repeats, and everything else is copied as it is.
+pub fn rle_encode(data: &[u8]) -> Vec<u8> {
+    const MIN_RUN: usize = 3;
+    const MAX_RUN: usize = 0x7f + MIN_RUN;
+    const MAX_LITERALS: usize = 0x80;
+
+    let mut out = Vec::new();
+    let mut literals: Vec<u8> = Vec::new();
+    let flush = |out: &mut Vec<u8>, literals: &mut Vec<u8>| {
+        for chunk in literals.chunks(MAX_LITERALS) {
+            out.push((chunk.len() - 1) as u8);
+            out.extend_from_slice(chunk);
+        }
+        literals.clear();
+    };
+    let mut i = 0;
+    while i < data.len() {
+        let run = data[i..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|&&b| b == data[i])
+            .count();
+        if run >= MIN_RUN {
+            flush(&mut out, &mut literals);
+            out.push(0x80 | (run - MIN_RUN) as u8);
+            out.push(data[i]);
+            i += run;
+        } else {
+            literals.push(data[i]);
+            i += 1;
+        }
+    }
+    flush(&mut out, &mut literals);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(data: &[u8]) -> Vec<u8> {
+        let encoded = rle_encode(data);
+        let mut decoded = vec![0; data.len()];
+        assert_eq!(rle_decode(&encoded, &mut decoded), data.len());
+        decoded
+    }
+
+    #[test]
+    fn empty() {
+        assert!(rle_encode(&[]).is_empty());
+        assert_eq!(round_trip(&[]), []);
+    }
+
+    #[test]
+    fn runs_longer_than_the_longest_repeat() {
+        // the longest repeat is 130 bytes, so these need several
+        let mut data = vec![0xaa; 300];
+        data.extend([0x55; 131]);
+        data.extend([0; 3]);
+        assert_eq!(round_trip(&data), data);
+        assert_eq!(
+            rle_encode(&[7; 131]),
+            [0xff, 7, 0x00, 7],
+            "a run one byte too long ends in a literal"
+        );
+    }
+
+    #[test]
+    fn literals_longer_than_the_longest_copy() {
+        // no byte repeats, and 300 is more than the 128 literal bytes a control byte can copy
+        let data: Vec<u8> = (0..300).map(|i| (i * 7 % 256) as u8).collect();
+        let encoded = rle_encode(&data);
+        assert_eq!(encoded.len(), data.len() + 3);
+        assert_eq!(round_trip(&data), data);
+    }
+
+    #[test]
+    fn runs_and_literals_mixed() {
+        let data = [1, 2, 2, 3, 3, 3, 4, 4, 4, 4, 5, 0, 0, 0, 0, 0, 0, 6, 7];
+        assert_eq!(round_trip(&data), data);
+        assert_eq!(
+            rle_encode(&data),
+            [0x02, 1, 2, 2, 0x80, 3, 0x81, 4, 0x00, 5, 0x83, 0, 0x01, 6, 7]
+        );
+    }
+}
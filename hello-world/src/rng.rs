@@ -0,0 +1,87 @@
+/// A xorshift32 random number generator. It gives the same numbers from the same seed on every
+/// machine, so netplay peers that share a seed stay in step.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    pub const fn new(seed: u32) -> Self {
+        // xorshift only ever gives 0 after 0
+        Self {
+            state: if seed == 0 { 0x2545_f491 } else { seed },
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state
+    }
+
+    /// A number from 0 up to but not including `n`, or 0 if `n` is 0.
+    pub fn below(&mut self, n: u32) -> u32 {
+        self.next_u32().checked_rem(n).unwrap_or(0)
+    }
+
+    /// A number from `min` to `max`, inclusive. The bounds can be given either way around.
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        let (min, max) = (min.min(max), min.max(max));
+        let span = max.abs_diff(min);
+        if span == u32::MAX {
+            return self.next_u32() as i32;
+        }
+        // wrapping, since the offset can be more than `i32::MAX` when `min` is negative
+        min.wrapping_add(self.below(span + 1) as i32)
+    }
+
+    /// A number from 0 up to but not including 1.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// True one time in `n`, on average.
+    pub fn one_in(&mut self, n: u32) -> bool {
+        self.below(n) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_stays_within_its_bounds() {
+        let mut rng = Rng::new(1);
+        for (min, max) in [
+            (-3, 3),
+            (5, 5),
+            (10, -10),
+            (i32::MIN, 0),
+            (-1, i32::MAX),
+            (i32::MAX - 1, i32::MAX),
+        ] {
+            for _ in 0..1000 {
+                let value = rng.range(min, max);
+                assert!(
+                    (min.min(max)..=min.max(max)).contains(&value),
+                    "{value} outside {min}..={max}"
+                );
+            }
+        }
+        // the full width of an i32 can't overflow
+        for _ in 0..1000 {
+            rng.range(i32::MIN, i32::MAX);
+        }
+    }
+
+    #[test]
+    fn range_covers_both_ends() {
+        let mut rng = Rng::new(7);
+        let values: Vec<i32> = (0..1000).map(|_| rng.range(-2, 2)).collect();
+        for value in -2..=2 {
+            assert!(values.contains(&value), "{value} never came up");
+        }
+    }
+}
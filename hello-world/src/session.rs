@@ -0,0 +1,137 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::{
+    input::{Gamepad, Input, InputFrame, Mouse},
+    rng::Rng,
+    wasm4,
+    wasm4_mmio::NETPLAY,
+};
+
+// bits of the NETPLAY register
+const NETPLAY_ACTIVE: u8 = 0b100;
+const NETPLAY_PLAYER: u8 = 0b011;
+
+/// Something that isn't kept the same between netplay peers, so game state that depends on it
+/// can drift apart.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Nondeterminism {
+    // only the gamepads are sent between peers
+    Mouse,
+    // each peer has its own save data
+    Disk,
+    // anything else the game reports with `Session::flag`
+    Other,
+}
+
+impl Nondeterminism {
+    const ALL: [Nondeterminism; 3] = [
+        Nondeterminism::Mouse,
+        Nondeterminism::Disk,
+        Nondeterminism::Other,
+    ];
+
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// The players in a game and their input, and whether they're playing over netplay. Under
+/// netplay every peer runs the whole game from the same input, so everything that affects game
+/// state has to come from the gamepads and the session's random number generator. Reading
+/// anything else, such as the mouse, through the session while netplay is on gets flagged and
+/// traced once, so desyncs can be tracked down.
+pub struct Session {
+    input: Input,
+    pub rng: Rng,
+    netplay: u8,
+    // a bit for each kind of nondeterminism used during netplay
+    flagged: AtomicU8,
+}
+
+impl Session {
+    /// Starts a session with the random number generator seeded with `seed`, which has to be the
+    /// same for every peer, such as a constant.
+    pub const fn new(seed: u32) -> Self {
+        Self {
+            input: Input::new(),
+            rng: Rng::new(seed),
+            netplay: 0,
+            flagged: AtomicU8::new(0),
+        }
+    }
+
+    /// Reads this frame's input and netplay state. Call once at the start of every frame.
+    pub fn update(&mut self) {
+        self.update_with(&InputFrame::read(), NETPLAY.read());
+    }
+
+    /// Takes this frame's input and `NETPLAY` register value from the given values instead of
+    /// reading them, such as to replay a recording.
+    pub fn update_with(&mut self, frame: &InputFrame, netplay: u8) {
+        self.input.update_with(frame);
+        self.netplay = netplay;
+    }
+
+    pub fn is_netplay(&self) -> bool {
+        self.netplay & NETPLAY_ACTIVE != 0
+    }
+
+    /// The index of the player on this machine, from 0 to 3. Without netplay every player is
+    /// local, and this is 0.
+    pub fn local_player(&self) -> usize {
+        if self.is_netplay() {
+            (self.netplay & NETPLAY_PLAYER) as usize
+        } else {
+            0
+        }
+    }
+
+    /// Whether `player` is playing on this machine, such as to decide whose view to draw.
+    pub fn is_local(&self, player: usize) -> bool {
+        !self.is_netplay() || player == self.local_player()
+    }
+
+    /// All of the input, including the mouse without flagging it, for things that don't affect
+    /// game state, such as menus only the local player sees.
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    /// The gamepad of player `player`, from 0 to 3, which is the same on every peer.
+    pub fn gamepad(&self, player: usize) -> &Gamepad {
+        self.input.gamepad(player)
+    }
+
+    /// The mouse, which only the local player's peer sees, so it's flagged during netplay.
+    pub fn mouse(&self) -> &Mouse {
+        self.flag(Nondeterminism::Mouse);
+        self.input.mouse()
+    }
+
+    /// Reads from persistent storage into `dest`, like `wasm4::diskr`, returning how many bytes
+    /// were read. Each peer has its own save data, so it's flagged during netplay.
+    pub fn read_disk(&self, dest: &mut [u8]) -> usize {
+        self.flag(Nondeterminism::Disk);
+        unsafe { wasm4::diskr(dest.as_mut_ptr(), dest.len() as u32) as usize }
+    }
+
+    /// Notes that something that isn't the same on every peer was used, tracing a warning the
+    /// first time each kind is used during netplay. Does nothing without netplay.
+    pub fn flag(&self, source: Nondeterminism) {
+        if !self.is_netplay()
+            || self.flagged.fetch_or(source.bit(), Ordering::Relaxed) & source.bit() != 0
+        {
+            return;
+        }
+        wasm4::trace(format!(
+            "netplay: {source:?} isn't the same on every peer, so game state may desync"
+        ));
+    }
+
+    /// The kinds of nondeterminism used during netplay so far.
+    pub fn flagged(&self) -> impl Iterator<Item = Nondeterminism> + '_ {
+        Nondeterminism::ALL
+            .into_iter()
+            .filter(|source| self.flagged.load(Ordering::Relaxed) & source.bit() != 0)
+    }
+}
@@ -72,6 +72,8 @@ pub struct MusicNote {
 }
 
 impl MusicNote {
+    // one argument per field
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         channel_mode: ChannelMode,
         frequency_start: Frequency,
@@ -289,7 +291,8 @@ impl<'a> SoundPlayer<'a> {
         play_id
     }
 
+    /// Stops the track started as `play_id`, so it plays no more notes.
     pub fn stop(&mut self, play_id: u32) {
-        todo!("Find the track in the list and stop it")
+        self.tracks.remove(&play_id);
     }
 }
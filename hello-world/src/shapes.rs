@@ -0,0 +1,295 @@
+use core::f32::consts::TAU;
+
+use crate::{framebuffer::Surface, geometry::Rect, pattern::Fill, wasm4_mmio::DRAW_COLORS};
+
+/// The color a DRAW_COLORS nibble selects, or `None` if it's transparent.
+fn nibble_color(draw_colors: u16, nibble: u32) -> Option<Fill> {
+    match (draw_colors >> (nibble * 4)) & 0xf {
+        0 => None,
+        color => Some(Fill::solid((color - 1) as u8 & 0b11)),
+    }
+}
+
+/// Draws shapes onto a surface in software. Like the host's own drawing functions, filled shapes
+/// are filled with `fill` and outlined with `outline`, and lines and curves are drawn with `fill`.
+/// Everything is clipped to the surface.
+pub struct Canvas<'a, S: Surface + ?Sized> {
+    surface: &'a mut S,
+    pub fill: Option<Fill>,
+    pub outline: Option<Fill>,
+}
+
+impl<'a, S: Surface + ?Sized> Canvas<'a, S> {
+    /// Takes the fill and outline colors from the first and second nibbles of `DRAW_COLORS`.
+    pub fn new(surface: &'a mut S) -> Self {
+        Self::with_draw_colors(surface, DRAW_COLORS.read())
+    }
+
+    pub fn with_draw_colors(surface: &'a mut S, draw_colors: u16) -> Self {
+        Self {
+            surface,
+            fill: nibble_color(draw_colors, 0),
+            outline: nibble_color(draw_colors, 1),
+        }
+    }
+
+    pub fn surface(&mut self) -> &mut S {
+        self.surface
+    }
+
+    fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.surface.width(), self.surface.height())
+    }
+
+    /// Fills a convex shape given by its corners, either clockwise or counter-clockwise, with
+    /// every pixel whose center lies inside it.
+    fn fill_convex(&mut self, points: &[(f32, f32)], fill: Fill) {
+        let min_y = points.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+        let max_y = points.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+        let first_row = min_y.round().max(0.0) as i32;
+        let last_row = max_y.round().min(self.surface.height() as f32 - 1.0) as i32;
+        for y in first_row..=last_row {
+            let row = y as f32;
+            let (mut left, mut right) = (f32::INFINITY, f32::NEG_INFINITY);
+            for (i, &(x1, y1)) in points.iter().enumerate() {
+                let (x2, y2) = points[(i + 1) % points.len()];
+                if (row < y1.min(y2) - 0.5) || (row > y1.max(y2) + 0.5) {
+                    continue;
+                }
+                let (x_a, x_b) = if (y2 - y1).abs() < f32::EPSILON {
+                    (x1, x2)
+                } else {
+                    // where the edge crosses this row, clamped to the edge's ends
+                    let t = ((row - y1) / (y2 - y1)).clamp(0.0, 1.0);
+                    let x = x1 + (x2 - x1) * t;
+                    (x, x)
+                };
+                left = left.min(x_a.min(x_b));
+                right = right.max(x_a.max(x_b));
+            }
+            if left <= right {
+                let (start, end) = (left.round() as i32, right.round() as i32);
+                fill.hline(self.surface, start, y, (end - start + 1) as u32);
+            }
+        }
+    }
+
+    /// Fills the pixels inside `bounds` for which `inside` is true, outlining the ones next to a
+    /// pixel that isn't.
+    fn fill_region(&mut self, bounds: Rect, inside: impl Fn(i32, i32) -> bool) {
+        let Some(area) = bounds.intersection(&self.bounds()) else {
+            return;
+        };
+        for y in area.y..area.bottom() {
+            for x in area.x..area.right() {
+                if !inside(x, y) {
+                    continue;
+                }
+                let edge =
+                    !(inside(x - 1, y) && inside(x + 1, y) && inside(x, y - 1) && inside(x, y + 1));
+                let fill = if edge {
+                    self.outline.or(self.fill)
+                } else {
+                    self.fill
+                };
+                if let Some(fill) = fill {
+                    fill.set_pixel(self.surface, x, y);
+                }
+            }
+        }
+    }
+
+    pub fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
+        if let Some(fill) = self.fill {
+            fill.line(self.surface, x1, y1, x2, y2);
+        }
+    }
+
+    /// Draws a line `thickness` pixels wide, centered on the line between the two points.
+    pub fn thick_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, thickness: u32) {
+        let Some(fill) = self.fill else {
+            return;
+        };
+        if thickness <= 1 {
+            fill.line(self.surface, x1, y1, x2, y2);
+            return;
+        }
+        let (dx, dy) = (x2 as f32 - x1 as f32, y2 as f32 - y1 as f32);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {
+            let half = thickness as i32 / 2;
+            fill.rect(self.surface, x1 - half, y1 - half, thickness, thickness);
+            return;
+        }
+        // offset each end to either side, half the thickness along the line's normal
+        let scale = (thickness as f32 - 1.0) / 2.0 / length;
+        let (normal_x, normal_y) = (-dy * scale, dx * scale);
+        let (x1, y1, x2, y2) = (x1 as f32, y1 as f32, x2 as f32, y2 as f32);
+        self.fill_convex(
+            &[
+                (x1 + normal_x, y1 + normal_y),
+                (x2 + normal_x, y2 + normal_y),
+                (x2 - normal_x, y2 - normal_y),
+                (x1 - normal_x, y1 - normal_y),
+            ],
+            fill,
+        );
+    }
+
+    pub fn triangle(&mut self, points: [(i32, i32); 3]) {
+        self.polygon(&points);
+    }
+
+    /// Draws a convex polygon given by its corners, either clockwise or counter-clockwise.
+    pub fn polygon(&mut self, points: &[(i32, i32)]) {
+        if points.is_empty() {
+            return;
+        }
+        if let Some(fill) = self.fill {
+            let points: Vec<(f32, f32)> =
+                points.iter().map(|&(x, y)| (x as f32, y as f32)).collect();
+            self.fill_convex(&points, fill);
+        }
+        if let Some(outline) = self.outline {
+            for (i, &(x1, y1)) in points.iter().enumerate() {
+                let (x2, y2) = points[(i + 1) % points.len()];
+                outline.line(self.surface, x1, y1, x2, y2);
+            }
+        }
+    }
+
+    /// Draws a rectangle with its corners rounded off to `radius` pixels.
+    pub fn rounded_rect(&mut self, x: i32, y: i32, width: u32, height: u32, radius: u32) {
+        let radius = radius.min(width / 2).min(height / 2) as f32;
+        let (right, bottom) = (width as f32 - radius, height as f32 - radius);
+        self.fill_region(Rect::new(x, y, width, height), |px, py| {
+            // distance from the pixel's center to the rectangle inset by the radius
+            let (px, py) = ((px - x) as f32 + 0.5, (py - y) as f32 + 0.5);
+            if px < 0.0 || py < 0.0 || px > width as f32 || py > height as f32 {
+                return false;
+            }
+            let dx = (radius - px).max(px - right).max(0.0);
+            let dy = (radius - py).max(py - bottom).max(0.0);
+            dx * dx + dy * dy <= radius * radius
+        });
+    }
+
+    /// Draws part of a circle's edge, going clockwise from `start_angle` to `end_angle`, in
+    /// radians from the positive x axis.
+    pub fn arc(&mut self, x: i32, y: i32, radius: u32, start_angle: f32, end_angle: f32) {
+        let Some(fill) = self.fill else {
+            return;
+        };
+        let sweep = arc_sweep(start_angle, end_angle);
+        // about one step per pixel along the edge
+        let steps = ((sweep * radius as f32).ceil() as u32).max(1);
+        let point = |step: u32| {
+            let angle = start_angle + sweep * step as f32 / steps as f32;
+            (
+                x + (angle.cos() * radius as f32).round() as i32,
+                y + (angle.sin() * radius as f32).round() as i32,
+            )
+        };
+        let mut previous = point(0);
+        for step in 1..=steps {
+            let next = point(step);
+            fill.line(self.surface, previous.0, previous.1, next.0, next.1);
+            previous = next;
+        }
+    }
+
+    /// Draws the slice of a circle going clockwise from `start_angle` to `end_angle`, in radians
+    /// from the positive x axis.
+    pub fn pie(&mut self, x: i32, y: i32, radius: u32, start_angle: f32, end_angle: f32) {
+        let sweep = arc_sweep(start_angle, end_angle);
+        let r = radius as f32;
+        let bounds = Rect::new(
+            x - radius as i32,
+            y - radius as i32,
+            radius * 2 + 1,
+            radius * 2 + 1,
+        );
+        self.fill_region(bounds, |px, py| {
+            let (dx, dy) = ((px - x) as f32, (py - y) as f32);
+            if dx * dx + dy * dy > r * (r + 1.0) {
+                return false;
+            }
+            if sweep >= TAU || (dx == 0.0 && dy == 0.0) {
+                return true;
+            }
+            (dy.atan2(dx) - start_angle).rem_euclid(TAU) <= sweep
+        });
+    }
+
+    /// Draws a quadratic Bézier curve from `start` to `end`, pulled towards `control`.
+    pub fn quadratic_bezier(&mut self, start: (i32, i32), control: (i32, i32), end: (i32, i32)) {
+        self.curve(&[start, control, end], |t| {
+            let u = 1.0 - t;
+            [u * u, 2.0 * u * t, t * t]
+        });
+    }
+
+    /// Draws a cubic Bézier curve from `start` to `end`, pulled towards the two control points.
+    pub fn cubic_bezier(
+        &mut self,
+        start: (i32, i32),
+        control1: (i32, i32),
+        control2: (i32, i32),
+        end: (i32, i32),
+    ) {
+        self.curve(&[start, control1, control2, end], |t| {
+            let u = 1.0 - t;
+            [u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t]
+        });
+    }
+
+    /// Draws a curve through `points` weighted by `weights` as `t` goes from 0 to 1, as a series
+    /// of short lines.
+    fn curve<const N: usize>(
+        &mut self,
+        points: &[(i32, i32); N],
+        weights: impl Fn(f32) -> [f32; N],
+    ) {
+        let Some(fill) = self.fill else {
+            return;
+        };
+        // the curve is never longer than its control polygon, so that many steps is enough
+        let length: f32 = points
+            .windows(2)
+            .map(|pair| {
+                let (dx, dy) = (
+                    (pair[1].0 - pair[0].0) as f32,
+                    (pair[1].1 - pair[0].1) as f32,
+                );
+                (dx * dx + dy * dy).sqrt()
+            })
+            .sum();
+        let steps = (length.ceil() as u32).max(1);
+        let point = |step: u32| {
+            let weights = weights(step as f32 / steps as f32);
+            let (mut x, mut y) = (0.0, 0.0);
+            for (weight, point) in weights.iter().zip(points) {
+                x += weight * point.0 as f32;
+                y += weight * point.1 as f32;
+            }
+            (x.round() as i32, y.round() as i32)
+        };
+        let mut previous = point(0);
+        for step in 1..=steps {
+            let next = point(step);
+            if next != previous {
+                fill.line(self.surface, previous.0, previous.1, next.0, next.1);
+                previous = next;
+            }
+        }
+    }
+}
+
+/// How far clockwise an arc goes, from 0 up to a full turn.
+fn arc_sweep(start_angle: f32, end_angle: f32) -> f32 {
+    if end_angle - start_angle >= TAU {
+        TAU
+    } else {
+        (end_angle - start_angle).rem_euclid(TAU)
+    }
+}
@@ -1,11 +1,22 @@
-use core::str;
-
 use crate::{
     wasm4::{self, BLIT_1BPP, BLIT_2BPP},
     wasm4_mmio,
 };
 
-pub trait Texture {}
+pub trait Texture {
+    // BLIT_1BPP or BLIT_2BPP, depending on how many bits each pixel takes up in `data`
+    const BLIT_FORMAT: u32;
+
+    fn data(&self) -> &[u8];
+    fn stride(&self) -> u32;
+
+    /// The color index of the pixel at `x`, `y`, counting from the top-left of the texture.
+    fn pixel(&self, x: u32, y: u32) -> u8 {
+        let bpp = if Self::BLIT_FORMAT == BLIT_2BPP { 2 } else { 1 };
+        let bit = ((y * self.stride() + x) * bpp) as usize;
+        (self.data()[bit / 8] >> (8 - bpp as usize - bit % 8)) & ((1 << bpp) - 1)
+    }
+}
 
 pub struct Texture2Color<'a> {
     pub data: &'a [u8],
@@ -13,7 +24,17 @@ pub struct Texture2Color<'a> {
     pub height: u32,
 }
 
-impl Texture for Texture2Color<'_> {}
+impl Texture for Texture2Color<'_> {
+    const BLIT_FORMAT: u32 = BLIT_1BPP;
+
+    fn data(&self) -> &[u8] {
+        self.data
+    }
+
+    fn stride(&self) -> u32 {
+        self.stride
+    }
+}
 
 pub struct Texture4Color<'a> {
     pub data: &'a [u8],
@@ -21,7 +42,17 @@ pub struct Texture4Color<'a> {
     pub height: u32,
 }
 
-impl Texture for Texture4Color<'_> {}
+impl Texture for Texture4Color<'_> {
+    const BLIT_FORMAT: u32 = BLIT_2BPP;
+
+    fn data(&self) -> &[u8] {
+        self.data
+    }
+
+    fn stride(&self) -> u32 {
+        self.stride
+    }
+}
 
 pub struct Sprite<'a, T: Texture> {
     pub texture: &'a T,
@@ -32,69 +63,19 @@ pub struct Sprite<'a, T: Texture> {
     pub draw_colors: u16,
 }
 
-impl<'a> Sprite<'a, Texture2Color<'a>> {
-    pub fn draw(&self, x: i32, y: i32, flags: u32) {
-        wasm4_mmio::DRAW_COLORS.write(self.draw_colors);
-        wasm4::blit_sub(
-            self.texture.data,
-            x,
-            y,
-            self.width,
-            self.height,
-            self.src_x,
-            self.src_y,
-            self.texture.stride,
-            flags | BLIT_1BPP,
-        );
-    }
-}
-
-impl<'a> Sprite<'a, Texture4Color<'a>> {
+impl<'a, T: Texture> Sprite<'a, T> {
     pub fn draw(&self, x: i32, y: i32, flags: u32) {
         wasm4_mmio::DRAW_COLORS.write(self.draw_colors);
         wasm4::blit_sub(
-            self.texture.data,
+            self.texture.data(),
             x,
             y,
             self.width,
             self.height,
             self.src_x,
             self.src_y,
-            self.texture.stride,
-            flags | BLIT_2BPP,
+            self.texture.stride(),
+            flags | T::BLIT_FORMAT,
         );
     }
 }
-
-pub struct SpriteFont<'a, T: Texture> {
-    // must be a texture where all 96 ASCII characters at 0x20 and above are in a horizontal line
-    pub texture: &'a T,
-    pub horizontal_padding: u32,
-    pub draw_colors: u16,
-}
-
-impl<'a> SpriteFont<'a, Texture2Color<'a>> {
-    pub fn draw_string(&self, s: &str, x: i32, y: i32) {
-        let char_width = self.texture.stride / 96;
-        let char_height = self.texture.height;
-        wasm4_mmio::DRAW_COLORS.write(self.draw_colors);
-        let mut x = x;
-        for c in s.chars() {
-            if c != ' ' {
-                let char_to_draw = if c.is_ascii_graphic() { c } else { 127 as char };
-                wasm4::blit_sub(
-                    self.texture.data,
-                    x,
-                    y,
-                    char_width,
-                    char_height,
-                    (char_to_draw as u32 - 0x20) * char_width,
-                    0,
-                    self.texture.stride,
-                    BLIT_1BPP,
-                );
-            }
-            x += (char_width + self.horizontal_padding) as i32;
-        }
-    }
-}
@@ -0,0 +1,321 @@
+use std::{borrow::Cow, ops::Range};
+
+use crate::{font::SpriteFont, sprite::Texture};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HorizontalAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VerticalAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// How a block of text is arranged inside a rectangle by `SpriteFont::draw_text`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextLayout<'a> {
+    pub horizontal_align: HorizontalAlign,
+    pub vertical_align: VerticalAlign,
+    /// Break lines at spaces (or mid-word, if a word is too long) to fit the rectangle's width
+    pub wrap: bool,
+    /// Appended to the last line that fits when the text overflows the rectangle, and to lines
+    /// that are too wide for it. Text that doesn't fit is cut off without it when this is `None`.
+    pub ellipsis: Option<&'a str>,
+}
+
+impl Default for TextLayout<'_> {
+    fn default() -> Self {
+        Self {
+            horizontal_align: HorizontalAlign::Left,
+            vertical_align: VerticalAlign::Top,
+            wrap: true,
+            ellipsis: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextItemKind {
+    /// Something visible that can't be broken across lines, like a character or an icon
+    Glyph,
+    /// A space, which lines can be broken at
+    Space,
+    Newline,
+    /// Takes up no room, like a markup tag
+    Control,
+}
+
+/// A piece of source text as far as layout is concerned.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextItem {
+    // byte range in the source text
+    pub start: usize,
+    pub end: usize,
+    pub kind: TextItemKind,
+    pub width: u32,
+    // the distance from the start of this item to the start of the next one
+    pub advance: i32,
+}
+
+/// The width of a line made of `items`.
+pub fn line_width(items: &[TextItem]) -> u32 {
+    let mut pen = 0;
+    let mut right = 0;
+    for item in items {
+        if let TextItemKind::Glyph | TextItemKind::Space = item.kind {
+            right = right.max(pen + item.width as i32);
+        }
+        pen += item.advance;
+    }
+    right.max(0) as u32
+}
+
+/// Splits `items` into lines at each newline, and also wherever a line would be wider than
+/// `max_width`. Returns the range of items in each line, leaving out the newlines and spaces
+/// that lines were broken at.
+pub fn wrap(items: &[TextItem], max_width: Option<u32>) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut pen = 0;
+    let mut word_break = None;
+    for (i, item) in items.iter().enumerate() {
+        match item.kind {
+            TextItemKind::Newline => {
+                lines.push(start..i);
+                start = i + 1;
+                pen = 0;
+                word_break = None;
+                continue;
+            }
+            TextItemKind::Space => word_break = Some(i),
+            TextItemKind::Glyph => match max_width {
+                Some(max_width) if pen > 0 && pen + item.width as i32 > max_width as i32 => {
+                    // break at the last space if there is one, otherwise split the word here
+                    lines.push(start..word_break.unwrap_or(i));
+                    start = word_break.map_or(i, |space| space + 1);
+                    word_break = None;
+                    pen = items[start..i].iter().map(|item| item.advance).sum();
+                    // the carried-over word can leave too little room for this glyph, so split
+                    // the word here as well
+                    if pen > 0 && pen + item.width as i32 > max_width as i32 {
+                        lines.push(start..i);
+                        start = i;
+                        pen = 0;
+                    }
+                }
+                _ => {}
+            },
+            TextItemKind::Control => {}
+        }
+        pen += item.advance;
+    }
+    lines.push(start..items.len());
+    lines
+}
+
+/// The number of `items` that fit in `max_width` while leaving `reserved` pixels after them.
+pub fn fit(items: &[TextItem], max_width: u32, reserved: u32) -> usize {
+    let mut pen = 0;
+    let mut count = 0;
+    for (i, item) in items.iter().enumerate() {
+        if item.kind == TextItemKind::Glyph {
+            if pen + (item.width + reserved) as i32 > max_width as i32 {
+                break;
+            }
+            count = i + 1;
+        }
+        pen += item.advance;
+    }
+    count
+}
+
+/// Breaks `s`, which has been split into `items`, into the lines that fit inside a `width` by
+/// `height` box.
+pub fn layout_lines<'t, T: Texture>(
+    font: &SpriteFont<T>,
+    s: &'t str,
+    items: &[TextItem],
+    width: u32,
+    height: u32,
+    layout: &TextLayout,
+) -> Vec<Cow<'t, str>> {
+    let max_width = if layout.wrap { Some(width) } else { None };
+    let mut lines = wrap(items, max_width);
+
+    let line_advance = font.glyph_height + font.line_spacing;
+    let max_lines = ((height + font.line_spacing) / line_advance) as usize;
+    let overflowed = lines.len() > max_lines;
+    lines.truncate(max_lines);
+
+    let source =
+        |range: &Range<usize>| match (items[range.clone()].first(), items[range.clone()].last()) {
+            (Some(first), Some(last)) => &s[first.start..last.end],
+            _ => "",
+        };
+    let last = lines.len().saturating_sub(1);
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, range)| {
+            let line_items = &items[range.clone()];
+            match layout.ellipsis {
+                Some(ellipsis) if (overflowed && i == last) || line_width(line_items) > width => {
+                    let reserved = font.horizontal_padding + font.measure_line(ellipsis);
+                    let count = fit(line_items, width, reserved);
+                    let kept = source(&(range.start..range.start + count));
+                    Cow::Owned([kept, ellipsis].concat())
+                }
+                // only happens without wrapping, or with a glyph wider than the whole box
+                None if line_width(line_items) > width => {
+                    let count = fit(line_items, width, 0);
+                    Cow::Borrowed(source(&(range.start..range.start + count)))
+                }
+                _ => Cow::Borrowed(source(range)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        font::{CharRange, Glyph},
+        sprite::Texture2Color,
+    };
+
+    // every character is 3 pixels wide with 1 pixel of padding, so each one advances 4, except
+    // for 'W', which is 8 pixels wide
+    const GLYPHS: [Glyph; 95] = {
+        let mut glyphs = [Glyph {
+            src_x: 0,
+            src_y: 0,
+            width: 3,
+        }; 95];
+        glyphs[(b'W' - b' ') as usize].width = 8;
+        glyphs
+    };
+    const CHAR_MAP: [CharRange; 1] = [CharRange {
+        first: ' ',
+        len: 95,
+        glyph_index: 0,
+    }];
+    const TEXTURE: Texture2Color = Texture2Color {
+        data: &[],
+        stride: 8,
+        height: 8,
+    };
+
+    fn font() -> SpriteFont<'static, Texture2Color<'static>> {
+        SpriteFont {
+            texture: &TEXTURE,
+            glyphs: &GLYPHS,
+            char_map: &CHAR_MAP,
+            fallback: '?',
+            glyph_height: 5,
+            kerning: &[],
+            horizontal_padding: 1,
+            line_spacing: 1,
+            draw_colors: 0x2,
+        }
+    }
+
+    fn wrapped(s: &str, max_width: Option<u32>) -> Vec<&str> {
+        let items = font().text_items(s);
+        wrap(&items, max_width)
+            .into_iter()
+            .map(|range| {
+                let start = items.get(range.start).map_or(s.len(), |item| item.start);
+                let end = items[..range.end].last().map_or(0, |item| item.end);
+                &s[start..end.max(start)]
+            })
+            .collect()
+    }
+
+    fn laid_out(s: &str, width: u32, height: u32, layout: &TextLayout) -> Vec<String> {
+        let font = font();
+        let items = font.text_items(s);
+        layout_lines(&font, s, &items, width, height, layout)
+            .into_iter()
+            .map(Cow::into_owned)
+            .collect()
+    }
+
+    #[test]
+    fn wrap_breaks_at_newlines() {
+        assert_eq!(wrapped("ab\ncd\n", None), ["ab", "cd", ""]);
+    }
+
+    #[test]
+    fn wrap_breaks_at_the_last_space() {
+        // "ab cd" is 19 pixels wide
+        assert_eq!(wrapped("ab cd ef", Some(19)), ["ab cd", "ef"]);
+        assert_eq!(wrapped("ab cd ef", Some(18)), ["ab", "cd", "ef"]);
+    }
+
+    #[test]
+    fn wrap_splits_words_longer_than_a_line() {
+        assert_eq!(wrapped("abcdefg", Some(11)), ["abc", "def", "g"]);
+    }
+
+    #[test]
+    fn wrap_splits_a_carried_over_word_that_still_doesnt_fit() {
+        // "bc" is carried onto the second line, where there's no room left after it for "W"
+        assert_eq!(wrapped("a bcW", Some(15)), ["a", "bc", "W"]);
+        for line in wrapped("a bcW", Some(15)) {
+            assert!(font().measure_line(line) <= 15);
+        }
+    }
+
+    #[test]
+    fn wrap_puts_at_least_one_glyph_on_each_line() {
+        assert_eq!(wrapped("ab", Some(1)), ["a", "b"]);
+    }
+
+    #[test]
+    fn fit_counts_the_glyphs_that_fit() {
+        let items = font().text_items("abcd");
+        assert_eq!(fit(&items, 11, 0), 3);
+        assert_eq!(fit(&items, 10, 0), 2);
+        assert_eq!(fit(&items, 11, 4), 2);
+        assert_eq!(fit(&items, 2, 0), 0);
+    }
+
+    #[test]
+    fn layout_lines_drops_lines_below_the_box() {
+        let layout = TextLayout::default();
+        // each line takes 5 pixels, plus 1 between lines
+        assert_eq!(laid_out("ab cd ef", 11, 11, &layout), ["ab", "cd"]);
+        assert_eq!(laid_out("ab cd ef", 11, 10, &layout), ["ab"]);
+    }
+
+    #[test]
+    fn layout_lines_ends_overflowing_text_with_the_ellipsis() {
+        let layout = TextLayout {
+            ellipsis: Some("."),
+            ..TextLayout::default()
+        };
+        assert_eq!(laid_out("ab cd ef", 11, 11, &layout), ["ab", "cd."]);
+        assert_eq!(laid_out("ab cd", 11, 11, &layout), ["ab", "cd"]);
+    }
+
+    #[test]
+    fn layout_lines_cuts_off_lines_too_wide_without_wrapping() {
+        let layout = TextLayout {
+            wrap: false,
+            ..TextLayout::default()
+        };
+        assert_eq!(laid_out("abcdef\nab", 11, 11, &layout), ["abc", "ab"]);
+
+        let layout = TextLayout {
+            ellipsis: Some("."),
+            ..layout
+        };
+        assert_eq!(laid_out("abcdef\nab", 11, 11, &layout), ["ab.", "ab"]);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{
+    geometry::{Rect, SCREEN_RECT},
+    sprite::Texture,
+    wasm4, wasm4_mmio,
+};
+
+// tiles with this index are left empty
+pub const EMPTY_TILE: u8 = u8::MAX;
+
+pub struct Tilemap<'a, T: Texture> {
+    // a texture with the tiles laid out in a grid, `tileset_columns` tiles across
+    pub tileset: &'a T,
+    pub tileset_columns: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    // tile indices in row-major order, `width` tiles across and `height` tiles down
+    pub tiles: &'a [u8],
+    pub width: u32,
+    pub height: u32,
+    pub draw_colors: u16,
+}
+
+impl<'a, T: Texture> Tilemap<'a, T> {
+    pub fn tile(&self, column: u32, row: u32) -> Option<u8> {
+        if column >= self.width || row >= self.height {
+            return None;
+        }
+        match self.tiles[(row * self.width + column) as usize] {
+            EMPTY_TILE => None,
+            tile => Some(tile),
+        }
+    }
+
+    /// The area covered by the map when its top-left corner is at `x`, `y`.
+    pub fn bounds(&self, x: i32, y: i32) -> Rect {
+        Rect::new(
+            x,
+            y,
+            self.width * self.tile_width,
+            self.height * self.tile_height,
+        )
+    }
+
+    /// Draws the map with its top-left corner at `x`, `y` on screen, skipping any tiles that
+    /// would be drawn entirely off screen.
+    pub fn draw(&self, x: i32, y: i32) {
+        let (tile_width, tile_height) = (self.tile_width as i32, self.tile_height as i32);
+        let first_column = ((SCREEN_RECT.x - x).max(0) / tile_width) as u32;
+        let first_row = ((SCREEN_RECT.y - y).max(0) / tile_height) as u32;
+        let last_column =
+            ((SCREEN_RECT.right() - x + tile_width - 1) / tile_width).clamp(0, self.width as i32);
+        let last_row = ((SCREEN_RECT.bottom() - y + tile_height - 1) / tile_height)
+            .clamp(0, self.height as i32);
+
+        wasm4_mmio::DRAW_COLORS.write(self.draw_colors);
+        for row in first_row..last_row as u32 {
+            for column in first_column..last_column as u32 {
+                if let Some(tile) = self.tile(column, row) {
+                    let tile = tile as u32;
+                    wasm4::blit_sub(
+                        self.tileset.data(),
+                        x + (column * self.tile_width) as i32,
+                        y + (row * self.tile_height) as i32,
+                        self.tile_width,
+                        self.tile_height,
+                        (tile % self.tileset_columns) * self.tile_width,
+                        (tile / self.tileset_columns) * self.tile_height,
+                        self.tileset.stride(),
+                        T::BLIT_FORMAT,
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{
+    framebuffer::Surface,
+    pattern::{Fill, Pattern},
+};
+
+/// Which way an effect moves across the screen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// A way of covering up the screen, applied to it after the scene is drawn. At progress 0 the
+/// screen is left as it is, and at progress 1 it's completely covered. `Transition` keeps progress
+/// between the two.
+pub trait Effect {
+    fn apply(&self, surface: &mut dyn Surface, progress: f32);
+}
+
+/// Covers the screen with `color`, starting from one edge and moving in `direction`.
+pub struct Wipe {
+    pub direction: Direction,
+    pub color: u8,
+}
+
+impl Effect for Wipe {
+    fn apply(&self, surface: &mut dyn Surface, progress: f32) {
+        let (width, height) = (surface.width(), surface.height());
+        let covered_width = (width as f32 * progress).round() as u32;
+        let covered_height = (height as f32 * progress).round() as u32;
+        match self.direction {
+            Direction::Right => surface.fill_rect(0, 0, covered_width, height, self.color),
+            Direction::Left => surface.fill_rect(
+                (width - covered_width) as i32,
+                0,
+                covered_width,
+                height,
+                self.color,
+            ),
+            Direction::Down => surface.fill_rect(0, 0, width, covered_height, self.color),
+            Direction::Up => surface.fill_rect(
+                0,
+                (height - covered_height) as i32,
+                width,
+                covered_height,
+                self.color,
+            ),
+        }
+    }
+}
+
+/// Covers everything outside a circle around `x`, `y` with `color`, shrinking the circle until
+/// nothing is left.
+pub struct Iris {
+    pub x: i32,
+    pub y: i32,
+    pub color: u8,
+}
+
+impl Effect for Iris {
+    fn apply(&self, surface: &mut dyn Surface, progress: f32) {
+        let (width, height) = (surface.width() as i32, surface.height() as i32);
+        // large enough to uncover the corner furthest from the center
+        let max_radius = [(0, 0), (width, 0), (0, height), (width, height)]
+            .iter()
+            .map(|&(x, y)| (((x - self.x).pow(2) + (y - self.y).pow(2)) as f32).sqrt())
+            .fold(0.0, f32::max);
+        let radius = max_radius * (1.0 - progress);
+        for y in 0..height {
+            let dy = y as f32 + 0.5 - self.y as f32;
+            let half_width = (radius * radius - dy * dy).max(0.0).sqrt();
+            let start = (self.x as f32 - half_width).round() as i32;
+            let end = (self.x as f32 + half_width).round() as i32;
+            if end > start {
+                surface.hline(0, y, start.max(0) as u32, self.color);
+                surface.hline(end, y, (width - end).max(0) as u32, self.color);
+            } else {
+                surface.hline(0, y, width as u32, self.color);
+            }
+        }
+    }
+}
+
+/// Pixelates the screen into blocks that grow to `max_block_size` pixels across.
+pub struct Mosaic {
+    pub max_block_size: u32,
+}
+
+impl Effect for Mosaic {
+    fn apply(&self, surface: &mut dyn Surface, progress: f32) {
+        let block_size = 1 + ((self.max_block_size.max(1) - 1) as f32 * progress).round() as u32;
+        if block_size == 1 {
+            return;
+        }
+        for y in (0..surface.height()).step_by(block_size as usize) {
+            for x in (0..surface.width()).step_by(block_size as usize) {
+                // take each block's color from its middle, clamped to the surface
+                let sample_x = (x + block_size / 2).min(surface.width() - 1);
+                let sample_y = (y + block_size / 2).min(surface.height() - 1);
+                if let Some(color) = surface.get_pixel(sample_x as i32, sample_y as i32) {
+                    surface.fill_rect(x as i32, y as i32, block_size, block_size, color);
+                }
+            }
+        }
+    }
+}
+
+/// Covers the screen with `color` a few scattered pixels at a time, using an ordered dither.
+pub struct Dissolve {
+    pub color: u8,
+}
+
+impl Effect for Dissolve {
+    fn apply(&self, surface: &mut dyn Surface, progress: f32) {
+        let level = (progress * 64.0).round() as u32;
+        let (width, height) = (surface.width(), surface.height());
+        Fill::pattern(Pattern::bayer(level), self.color).rect(surface, 0, 0, width, height);
+    }
+}
+
+/// Pushes the screen off in `direction`, filling the space it leaves behind with `color`.
+pub struct Slide {
+    pub direction: Direction,
+    pub color: u8,
+}
+
+impl Effect for Slide {
+    fn apply(&self, surface: &mut dyn Surface, progress: f32) {
+        let (width, height) = (surface.width() as i32, surface.height() as i32);
+        let (dx, dy) = match self.direction {
+            Direction::Left => (-(width as f32 * progress).round() as i32, 0),
+            Direction::Right => ((width as f32 * progress).round() as i32, 0),
+            Direction::Up => (0, -(height as f32 * progress).round() as i32),
+            Direction::Down => (0, (height as f32 * progress).round() as i32),
+        };
+        if dx == 0 && dy == 0 {
+            return;
+        }
+        // walk against the direction of movement, so every pixel is read before it's overwritten
+        for row in 0..height {
+            let y = if dy > 0 { height - 1 - row } else { row };
+            for column in 0..width {
+                let x = if dx > 0 { width - 1 - column } else { column };
+                let color = surface.get_pixel(x - dx, y - dy).unwrap_or(self.color);
+                surface.set_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TransitionDirection {
+    // covers the screen up, going from progress 0 to 1
+    Out,
+    // uncovers the screen, going from progress 1 to 0
+    In,
+}
+
+/// Runs an effect over a number of frames.
+pub struct Transition {
+    effect: Box<dyn Effect + Sync>,
+    direction: TransitionDirection,
+    frames: u32,
+    frame: u32,
+}
+
+impl Transition {
+    pub fn new(
+        effect: Box<dyn Effect + Sync>,
+        direction: TransitionDirection,
+        frames: u32,
+    ) -> Self {
+        Self {
+            effect,
+            direction,
+            frames,
+            frame: 0,
+        }
+    }
+
+    /// How far the screen is covered, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        let progress = if self.frames == 0 {
+            1.0
+        } else {
+            self.frame as f32 / self.frames as f32
+        };
+        let progress = match self.direction {
+            TransitionDirection::Out => progress,
+            TransitionDirection::In => 1.0 - progress,
+        };
+        progress.clamp(0.0, 1.0)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.frames
+    }
+
+    /// Advances by one frame, then applies the effect to `surface`, usually the screen after the
+    /// scene has been drawn. Once finished, it keeps applying the effect at its final progress,
+    /// so after going out the screen stays covered until the next scene takes over.
+    pub fn update(&mut self, surface: &mut dyn Surface) {
+        if !self.is_finished() {
+            self.frame += 1;
+        }
+        self.effect.apply(surface, self.progress());
+    }
+}
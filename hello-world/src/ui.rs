@@ -0,0 +1,329 @@
+use crate::{
+    font::SpriteFont,
+    geometry::Rect,
+    input::{Button, Input, MouseButton},
+    sprite::Texture,
+    wasm4,
+    wasm4_mmio::DRAW_COLORS,
+};
+
+// space between a widget's frame and its text
+const PADDING: u32 = 2;
+
+/// `DRAW_COLORS` values for drawing widgets. Frames are drawn with `rect`, so their first nibble
+/// is the fill and their second the outline.
+pub struct UiStyle {
+    pub text_colors: u16,
+    pub focused_text_colors: u16,
+    pub frame_colors: u16,
+    pub focused_frame_colors: u16,
+    // drawn to the left of the focused item in a menu
+    pub cursor: char,
+}
+
+/// How far a scroll list is scrolled, kept between frames by whoever owns the list.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ScrollList {
+    pub scroll: usize,
+}
+
+/// An immediate-mode UI: widgets are drawn and handle their input in the same call, once per
+/// frame, between `begin` and the end of the frame. Every widget that can be focused takes the
+/// next place in the focus order, which the D-pad moves up and down through. Button 1 or a left
+/// click activates the focused widget, and pointing at a widget with the mouse focuses it.
+pub struct Ui<'a, T: Texture> {
+    pub font: &'a SpriteFont<'a, T>,
+    pub style: UiStyle,
+    focus: usize,
+    // how many focusable widgets have been drawn this frame, and how many there were last frame
+    widget_count: usize,
+    last_widget_count: usize,
+    input: Input,
+    // whether the focus moved with the D-pad this frame, so scroll lists follow it
+    focus_moved: bool,
+}
+
+impl<'a, T: Texture> Ui<'a, T> {
+    pub fn new(font: &'a SpriteFont<'a, T>, style: UiStyle) -> Self {
+        Self {
+            font,
+            style,
+            focus: 0,
+            widget_count: 0,
+            last_widget_count: 0,
+            input: Input::new(),
+            focus_moved: false,
+        }
+    }
+
+    /// Takes this frame's input, using the first gamepad and the mouse, and moves the focus. Call
+    /// once per frame, after updating `input` and before any widgets.
+    pub fn begin(&mut self, input: &Input) {
+        self.input = *input;
+
+        self.last_widget_count = self.widget_count;
+        self.widget_count = 0;
+        self.focus_moved = false;
+        if self.last_widget_count > 0 {
+            if self.pressed(Button::Up) {
+                self.focus = (self.focus + self.last_widget_count - 1) % self.last_widget_count;
+                self.focus_moved = true;
+            }
+            if self.pressed(Button::Down) {
+                self.focus = (self.focus + 1) % self.last_widget_count;
+                self.focus_moved = true;
+            }
+            self.focus = self.focus.min(self.last_widget_count - 1);
+        }
+    }
+
+    /// The place in the focus order of the focused widget.
+    pub fn focus(&self) -> usize {
+        self.focus
+    }
+
+    pub fn set_focus(&mut self, focus: usize) {
+        self.focus = focus;
+    }
+
+    fn pressed(&self, button: Button) -> bool {
+        self.input.gamepad(0).pressed(button)
+    }
+
+    fn clicked(&self) -> bool {
+        self.input.mouse().pressed(MouseButton::Left)
+    }
+
+    fn hovering(&self, rect: &Rect) -> bool {
+        self.input.mouse().hovering(rect)
+    }
+
+    /// Gives the next place in the focus order to a widget covering `rect`, returning whether
+    /// it's focused and whether it was activated this frame.
+    fn widget(&mut self, rect: Rect) -> (bool, bool) {
+        let id = self.widget_count;
+        self.widget_count += 1;
+        let hovering = self.hovering(&rect);
+        if hovering && (self.input.mouse().moved() || self.clicked()) {
+            self.focus = id;
+        }
+        let focused = self.focus == id;
+        let activated = focused && (self.pressed(Button::X) || (hovering && self.clicked()));
+        (focused, activated)
+    }
+
+    fn text_colors(&self, focused: bool) -> u16 {
+        if focused {
+            self.style.focused_text_colors
+        } else {
+            self.style.text_colors
+        }
+    }
+
+    fn frame_colors(&self, focused: bool) -> u16 {
+        if focused {
+            self.style.focused_frame_colors
+        } else {
+            self.style.frame_colors
+        }
+    }
+
+    fn draw_frame(&self, rect: Rect, focused: bool) {
+        DRAW_COLORS.write(self.frame_colors(focused));
+        wasm4::rect(rect.x, rect.y, rect.width, rect.height);
+    }
+
+    /// Fills `rect` with the color frames are outlined in.
+    fn draw_mark(&self, rect: Rect, focused: bool) {
+        DRAW_COLORS.write((self.frame_colors(focused) >> 4) & 0xf);
+        wasm4::rect(rect.x, rect.y, rect.width, rect.height);
+    }
+
+    /// Draws one line of text in the middle of `rect` vertically, and at `x` horizontally.
+    fn draw_line(&self, text: &str, x: i32, rect: Rect, colors: u16) {
+        let y = rect.y + (rect.height as i32 - self.font.glyph_height as i32) / 2;
+        DRAW_COLORS.write(colors);
+        self.font.blit_string(text, x, y);
+    }
+
+    /// The height of one row of a menu or list.
+    pub fn row_height(&self) -> u32 {
+        self.font.glyph_height + PADDING * 2
+    }
+
+    pub fn label(&self, text: &str, x: i32, y: i32) {
+        DRAW_COLORS.write(self.style.text_colors);
+        self.font.blit_string(text, x, y);
+    }
+
+    /// Draws a button, returning whether it was pressed this frame.
+    pub fn button(&mut self, text: &str, rect: Rect) -> bool {
+        let (focused, activated) = self.widget(rect);
+        self.draw_frame(rect, focused);
+        let x = rect.x + (rect.width as i32 - self.font.measure_line(text) as i32) / 2;
+        self.draw_line(text, x, rect, self.text_colors(focused));
+        activated
+    }
+
+    /// Draws a labelled checkbox, flipping `value` and returning true when it's pressed.
+    pub fn toggle(&mut self, text: &str, rect: Rect, value: &mut bool) -> bool {
+        let (focused, activated) = self.widget(rect);
+        if activated {
+            *value = !*value;
+        }
+        let colors = self.text_colors(focused);
+        self.draw_line(text, rect.x + PADDING as i32, rect, colors);
+
+        let size = self.font.glyph_height;
+        let box_rect = Rect::new(
+            rect.right() - (size + PADDING) as i32,
+            rect.y + (rect.height as i32 - size as i32) / 2,
+            size,
+            size,
+        );
+        self.draw_frame(box_rect, focused);
+        if *value {
+            let check = Rect::new(
+                box_rect.x + 2,
+                box_rect.y + 2,
+                size.saturating_sub(4),
+                size.saturating_sub(4),
+            );
+            self.draw_mark(check, focused);
+        }
+        activated
+    }
+
+    /// Draws a labelled slider for `value` between `min` and `max`, which moves by `step` with
+    /// left and right on the D-pad or follows the mouse while it's held down on the track.
+    /// Returns whether the value changed. The bounds are swapped if `min` is above `max`.
+    pub fn slider(
+        &mut self,
+        text: &str,
+        rect: Rect,
+        value: &mut i32,
+        min: i32,
+        max: i32,
+        step: i32,
+    ) -> bool {
+        let (focused, _) = self.widget(rect);
+        let (min, max) = (min.min(max), min.max(max));
+        let old_value = *value;
+        let colors = self.text_colors(focused);
+        self.draw_line(text, rect.x + PADDING as i32, rect, colors);
+
+        // the track takes up the right half of the widget
+        let track = Rect::new(
+            rect.x + rect.width as i32 / 2,
+            rect.y,
+            (rect.width / 2).saturating_sub(PADDING),
+            rect.height,
+        );
+        if focused {
+            if self.pressed(Button::Left) {
+                *value = value.saturating_sub(step);
+            }
+            if self.pressed(Button::Right) {
+                *value = value.saturating_add(step);
+            }
+            if self.input.mouse().held(MouseButton::Left) && self.hovering(&track) && max > min {
+                // in i64, since the range between the bounds can be wider than an i32
+                let last_x = (track.width as i64 - 1).max(0);
+                let offset = (self.input.mouse().x as i64 - track.x as i64).clamp(0, last_x);
+                let step = step.max(1) as i64;
+                let steps = (max as i64 - min as i64) / step;
+                let nearest_step = (offset * steps + last_x / 2) / last_x.max(1);
+                *value = (min as i64 + nearest_step * step) as i32;
+            }
+        }
+        *value = (*value).clamp(min, max);
+
+        let middle = track.y + track.height as i32 / 2;
+        self.draw_mark(Rect::new(track.x, middle, track.width, 1), focused);
+        let range = (max as i64 - min as i64).max(1);
+        let travel = (track.width as i64 - 3).max(0);
+        let knob_x = track.x + ((*value as i64 - min as i64) * travel / range) as i32;
+        self.draw_frame(Rect::new(knob_x, middle - 3, 3, 7), focused);
+        *value != old_value
+    }
+
+    /// Draws a vertical menu with a cursor beside the focused item, returning the index of the
+    /// item chosen this frame.
+    pub fn menu(&mut self, items: &[&str], x: i32, y: i32) -> Option<usize> {
+        let cursor_width = self.font.advance(self.style.cursor, None).max(0) as u32 + PADDING;
+        let width = cursor_width
+            + items
+                .iter()
+                .map(|item| self.font.measure_line(item))
+                .max()
+                .unwrap_or(0);
+        let mut chosen = None;
+        for (i, item) in items.iter().enumerate() {
+            let row = Rect::new(
+                x,
+                y + (i as u32 * self.row_height()) as i32,
+                width,
+                self.row_height(),
+            );
+            let (focused, activated) = self.widget(row);
+            if activated {
+                chosen = Some(i);
+            }
+            let colors = self.text_colors(focused);
+            if focused {
+                let mut cursor = [0; 4];
+                self.draw_line(self.style.cursor.encode_utf8(&mut cursor), x, row, colors);
+            }
+            self.draw_line(item, x + cursor_width as i32, row, colors);
+        }
+        chosen
+    }
+
+    /// Draws as many of `items` as fit inside `rect`, scrolling to keep the focused one in view,
+    /// and returns the index of the item chosen this frame.
+    pub fn scroll_list(
+        &mut self,
+        items: &[&str],
+        rect: Rect,
+        list: &mut ScrollList,
+    ) -> Option<usize> {
+        let visible_rows = (rect.height / self.row_height()).max(1) as usize;
+        let first_id = self.widget_count;
+        if self.focus_moved && (first_id..first_id + items.len()).contains(&self.focus) {
+            let focused_item = self.focus - first_id;
+            if focused_item < list.scroll {
+                list.scroll = focused_item;
+            } else if focused_item >= list.scroll + visible_rows {
+                list.scroll = focused_item + 1 - visible_rows;
+            }
+        }
+        list.scroll = list.scroll.min(items.len().saturating_sub(visible_rows));
+
+        self.draw_frame(rect, false);
+        let mut chosen = None;
+        for (i, item) in items.iter().enumerate() {
+            let visible = (list.scroll..list.scroll + visible_rows).contains(&i);
+            // items scrolled out of view keep their place in the focus order, but can't be
+            // pointed at with the mouse
+            let row = if visible {
+                Rect::new(
+                    rect.x,
+                    rect.y + ((i - list.scroll) as u32 * self.row_height()) as i32,
+                    rect.width,
+                    self.row_height(),
+                )
+            } else {
+                Rect::default()
+            };
+            let (focused, activated) = self.widget(row);
+            if activated {
+                chosen = Some(i);
+            }
+            if visible {
+                let colors = self.text_colors(focused);
+                self.draw_line(item, rect.x + PADDING as i32, row, colors);
+            }
+        }
+        chosen
+    }
+}